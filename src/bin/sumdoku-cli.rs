@@ -0,0 +1,198 @@
+//! Generates, solves and validates puzzles without opening a window.
+//!
+//! ```text
+//! sumdoku-cli generate [options]   puzzles to stdout, or files with --out
+//!     --count N          how many puzzles (1)
+//!     --seed S           seed of the first puzzle, the next ones use S+1...
+//!     --size N           4, 6, 8, 9 or 12 (9)
+//!     --difficulty D     easy, medium, hard or fiendish (medium)
+//!     --max-cage N       largest cage (6)
+//!     --json             write the f-puzzles JSON instead of text
+//!     --out DIR          write puzzle-001.txt, ... into DIR
+//! sumdoku-cli solve FILE...        prints each solution grid
+//! sumdoku-cli validate FILE...     checks each puzzle has one solution
+//! sumdoku-cli print FILE OUT [--solution]
+//!                                  writes OUT as SVG or PDF, by its extension,
+//!                                  with the solution as well if asked
+//! sumdoku-cli --help               prints the usage
+//! ```
+//!
+//! Errors go to stderr and end the program with exit code 2.
+//!
+//! Files ending in `.json` are read as f-puzzles or SudokuPad JSON, others
+//! in the text format.
+
+use std::{fs, path::PathBuf};
+
+use rand::{thread_rng, Rng};
+use sumdoku::logic::{rate, solve, Difficulty, Shape, Solution, SumdokuBoard};
+
+const USAGE: &str = "usage: sumdoku-cli generate [--count N] [--seed S] [--size N] \
+[--difficulty D] [--max-cage N] [--json] [--out DIR]
+       sumdoku-cli solve FILE...
+       sumdoku-cli validate FILE...
+       sumdoku-cli print FILE OUT.svg|OUT.pdf [--solution]";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("generate") => generate(&args[1..]),
+        Some("solve") => solve_files(&args[1..]),
+        Some("validate") => validate_files(&args[1..]),
+        Some("print") => print(&args[1..]),
+        Some("-h" | "--help" | "help") => {
+            println!("{}", USAGE);
+            return;
+        }
+        _ => Err(USAGE.to_string()),
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    }
+}
+
+fn generate(args: &[String]) -> Result<(), String> {
+    let mut count = 1;
+    let mut seed: u64 = thread_rng().gen();
+    let mut shape = Shape::CLASSIC;
+    let mut difficulty = Difficulty::Medium;
+    let mut max_cage_size = 6;
+    let mut json = false;
+    let mut out = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--count" => count = parse(value()?)?,
+            "--seed" => seed = parse(value()?)?,
+            "--size" => {
+                let size = parse(value()?)?;
+                shape = Shape::for_size(size).ok_or(format!("unsupported grid size {}", size))?;
+            }
+            "--difficulty" => difficulty = value()?.parse()?,
+            "--max-cage" => max_cage_size = parse(value()?)?,
+            "--json" => json = true,
+            "--out" => out = Some(PathBuf::from(value()?)),
+            _ => return Err(format!("unknown option {}\n{}", arg, USAGE)),
+        }
+    }
+    if max_cage_size == 0 {
+        return Err("--max-cage must be at least 1".to_string());
+    }
+    if let Some(dir) = &out {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    for i in 0..count {
+        let seed = seed.wrapping_add(i as u64);
+        let board = SumdokuBoard::from_seed_with_difficulty(seed, shape, max_cage_size, difficulty);
+        let text = if json {
+            board.to_json() + "\n"
+        } else {
+            format!("# seed {}, {}\n{}", seed, board.difficulty, board.to_text())
+        };
+        match &out {
+            Some(dir) => {
+                let extension = if json { "json" } else { "txt" };
+                let path = dir.join(format!("puzzle-{:03}.{}", i + 1, extension));
+                fs::write(&path, text).map_err(|e| e.to_string())?;
+            }
+            None if i > 0 => print!("\n{}", text),
+            None => print!("{}", text),
+        }
+    }
+    Ok(())
+}
+
+fn solve_files(files: &[String]) -> Result<(), String> {
+    if files.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    for (i, file) in files.iter().enumerate() {
+        let board = read(file).map_err(|e| format!("{}: {}", file, e))?;
+        let Solution::Unique(grid) = solve(&board) else {
+            return Err(format!("{}: no unique solution", file));
+        };
+        if i > 0 {
+            println!();
+        }
+        if files.len() > 1 {
+            println!("# {}", file);
+        }
+        let separator = if board.shape.size > 9 { " " } else { "" };
+        for row in grid {
+            let digits: Vec<String> = row.iter().map(|d| d.to_string()).collect();
+            println!("{}", digits.join(separator));
+        }
+    }
+    Ok(())
+}
+
+/// Reports every file, then fails if any of them is not a proper puzzle.
+fn validate_files(files: &[String]) -> Result<(), String> {
+    if files.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    let mut failed = 0;
+    for file in files {
+        match read(file).and_then(|board| check(&board)) {
+            Ok(difficulty) => println!("{}: ok, {}", file, difficulty),
+            Err(e) => {
+                println!("{}: {}", file, e);
+                failed += 1;
+            }
+        }
+    }
+    match failed {
+        0 => Ok(()),
+        _ => Err(format!("{} of {} puzzles invalid", failed, files.len())),
+    }
+}
+
+/// Writes a puzzle as SVG or PDF. An SVG holds a single page, so with
+/// `--solution` it shows the solution instead of the puzzle.
+fn print(args: &[String]) -> Result<(), String> {
+    let solution = args.iter().any(|arg| arg == "--solution");
+    let files: Vec<&String> = args.iter().filter(|arg| *arg != "--solution").collect();
+    let [file, out] = files[..] else {
+        return Err(USAGE.to_string());
+    };
+    let board = read(file).map_err(|e| format!("{}: {}", file, e))?;
+    let data = if out.ends_with(".svg") {
+        board.to_svg(solution).into_bytes()
+    } else if out.ends_with(".pdf") {
+        board.to_pdf(solution)
+    } else {
+        return Err(format!("{}: expected a .svg or .pdf file", out));
+    };
+    fs::write(out, data).map_err(|e| format!("{}: {}", out, e))
+}
+
+/// Checks that the cages lead to exactly one grid and that it is the
+/// solution the board gives, and rates the puzzle.
+fn check(board: &SumdokuBoard) -> Result<Difficulty, String> {
+    board.validate()?;
+    match solve(board) {
+        Solution::None => Err("no solution".to_string()),
+        Solution::Multiple(..) => Err("more than one solution".to_string()),
+        Solution::Unique(grid) if grid != board.solution => {
+            Err("solution does not match the cages".to_string())
+        }
+        Solution::Unique(_) => Ok(rate(board)),
+    }
+}
+
+fn read(file: &str) -> Result<SumdokuBoard, String> {
+    let text = fs::read_to_string(file).map_err(|e| e.to_string())?;
+    if file.ends_with(".json") {
+        SumdokuBoard::from_json(&text)
+    } else {
+        SumdokuBoard::from_text(&text)
+    }
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid number {}", value))
+}
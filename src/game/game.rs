@@ -1,18 +1,115 @@
-use std::time::Instant;
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
 
-use crate::logic::SumdokuBoard;
+use super::{CellChange, History, Move, MoveKind};
+use crate::logic::{combination_mask, Cell, Difficulty, HumanSolver, Shape, SumdokuBoard};
 
 pub struct Game {
     pub board: SumdokuBoard,
     pub mistakes: u8,
     pub cells: Vec<Vec<GameCell>>,
-    pub time: Instant,
-    undo_state: BoardState,
+    /// Play time up to the last pause.
+    played: Duration,
+    /// When play last started or resumed, or `None` while paused.
+    running_since: Option<Instant>,
+    /// How long the grid took, set once every digit matches the solution.
+    pub finished: Option<Duration>,
+    /// Hints given so far.
+    pub hints: u32,
+    /// Keeps notes in line with the candidates as digits are placed.
+    pub auto_notes: bool,
+    pub check_mode: CheckMode,
+    pub history: History,
+    /// Digits ruled out per cell by hints given so far.
+    pub(super) excluded: Vec<Vec<u16>>,
+    /// Cells changed so far by the move being recorded.
+    changes: Vec<CellChange>,
 }
 
-type BoardState = Vec<Vec<Vec<GameCell>>>;
+/// What new puzzles are generated from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    pub shape: Shape,
+    pub max_cage_size: usize,
+    pub difficulty: Difficulty,
+}
+
+impl Options {
+    pub const CAGE_SIZES: std::ops::RangeInclusive<usize> = 2..=9;
+
+    pub fn new(shape: Shape) -> Self {
+        Options {
+            shape,
+            max_cage_size: 6,
+            difficulty: Difficulty::Medium,
+        }
+    }
+}
+
+/// How much the game tells the player about wrong digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckMode {
+    /// Digits are checked against the solution as they are placed, and
+    /// every wrong one counts as a mistake.
+    Immediate,
+    /// Only digits breaking a rule on the grid are shown.
+    Conflicts,
+    /// Nothing is shown until the grid is full.
+    Off,
+}
 
-#[derive(Clone)]
+impl CheckMode {
+    pub fn next(self) -> Self {
+        match self {
+            CheckMode::Immediate => CheckMode::Conflicts,
+            CheckMode::Conflicts => CheckMode::Off,
+            CheckMode::Off => CheckMode::Immediate,
+        }
+    }
+}
+
+impl Display for CheckMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            CheckMode::Immediate => "Check: instant",
+            CheckMode::Conflicts => "Check: rules",
+            CheckMode::Off => "Check: off",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Which rule a conflict breaks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    Row,
+    Column,
+    Box,
+    /// A digit repeated within a cage.
+    Cage,
+    /// A cage whose digits cannot add up to its sum.
+    CageSum,
+}
+
+/// A broken rule and the cells that break it.
+#[derive(Debug)]
+pub struct Conflict {
+    pub kind: ConflictKind,
+    pub cells: Vec<Cell>,
+}
+
+/// A hint for the player: what to look at and why.
+pub struct Hint {
+    pub text: String,
+    /// Cells the hint rests on.
+    pub cells: Vec<Cell>,
+    /// Cells the hint says something about.
+    pub targets: Vec<Cell>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct GameCell {
     pub notes: u16,
     pub value: u32,
@@ -39,64 +136,664 @@ impl GameCell {
 }
 
 impl Game {
-    pub fn new() -> Self {
+    pub fn new(shape: Shape) -> Self {
+        Game::with_options(Options::new(shape))
+    }
+
+    pub fn with_options(options: Options) -> Self {
+        Game::new_with_board(SumdokuBoard::with_difficulty(
+            options.shape,
+            options.max_cage_size,
+            options.difficulty,
+        ))
+    }
+
+    /// A fresh game on the given board.
+    pub fn new_with_board(board: SumdokuBoard) -> Self {
+        let shape = board.shape;
         Game {
-            board: SumdokuBoard::new(6),
+            board,
             mistakes: 0,
-            cells: vec![vec![GameCell::new(); 9]; 9],
-            time: Instant::now(),
-            undo_state: vec![],
+            cells: vec![vec![GameCell::new(); shape.size]; shape.size],
+            played: Duration::ZERO,
+            running_since: Some(Instant::now()),
+            finished: None,
+            hints: 0,
+            auto_notes: false,
+            check_mode: CheckMode::Immediate,
+            history: History::default(),
+            excluded: vec![vec![0; shape.size]; shape.size],
+            changes: Vec::new(),
+        }
+    }
+
+    /// Starts a fresh puzzle, keeping the player's settings.
+    pub fn new_game(&mut self, options: Options) {
+        let mut game = Game::with_options(options);
+        game.auto_notes = self.auto_notes;
+        game.check_mode = self.check_mode;
+        *self = game;
+    }
+
+    /// Clears the grid to play the same puzzle again.
+    pub fn replay(&mut self) {
+        let size = self.board.shape.size;
+        self.mistakes = 0;
+        self.cells = vec![vec![GameCell::new(); size]; size];
+        self.played = Duration::ZERO;
+        self.running_since = Some(Instant::now());
+        self.finished = None;
+        self.hints = 0;
+        self.history = History::default();
+        self.excluded = vec![vec![0; size]; size];
+    }
+
+    /// Whether the grid has been solved.
+    pub fn is_complete(&self) -> bool {
+        self.finished.is_some()
+    }
+
+    /// Stops the clock.
+    pub fn pause(&mut self) {
+        if let Some(since) = self.running_since.take() {
+            self.played += since.elapsed();
         }
     }
 
+    /// Starts the clock again, unless the grid is solved.
+    pub fn resume(&mut self) {
+        if self.running_since.is_none() && !self.is_complete() {
+            self.running_since = Some(Instant::now());
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.running_since.is_none() && !self.is_complete()
+    }
+
+    /// Sets the time played so far, as when loading a saved game.
+    pub fn set_elapsed(&mut self, elapsed: Duration) {
+        self.played = elapsed;
+        if self.running_since.is_some() {
+            self.running_since = Some(Instant::now());
+        }
+    }
+
+    /// Time spent playing, not counting pauses, frozen once the grid is
+    /// solved.
+    pub fn elapsed(&self) -> Duration {
+        let running = self
+            .running_since
+            .map_or(Duration::ZERO, |since| since.elapsed());
+        self.finished.unwrap_or(self.played + running)
+    }
+
+    /// Finds the next logical step on the current grid. A wrong digit is
+    /// pointed out first, since nothing can be deduced from it: any digit
+    /// off the solution when checking immediately, otherwise only digits
+    /// breaking a rule, so that the hint gives away no more than the check
+    /// mode does. When no step is found, the digit of the active cell, or
+    /// else of the first empty cell, is revealed.
+    pub fn hint(&mut self, active: Option<(usize, usize)>) -> Option<Hint> {
+        let hint = self.next_hint(active);
+        if hint.is_some() {
+            self.hints += 1;
+        }
+        hint
+    }
+
+    fn next_hint(&mut self, active: Option<(usize, usize)>) -> Option<Hint> {
+        let size = self.board.shape.size;
+        let cells = (0..size).flat_map(|r| (0..size).map(move |c| (r, c)));
+        if self.check_mode == CheckMode::Immediate {
+            if let Some((r, c)) = cells.clone().find(|&(r, c)| {
+                let value = self.cells[r][c].value;
+                value != 0 && value != self.board.solution[r][c]
+            }) {
+                return Some(Hint {
+                    text: format!("r{}c{} is wrong", r + 1, c + 1),
+                    cells: vec![],
+                    targets: vec![Cell::new(r, c)],
+                });
+            }
+        } else if let Some(conflict) = self.conflicts().into_iter().next() {
+            return Some(conflict_hint(conflict));
+        }
+
+        // steps rest only on digits matching the solution, since what they
+        // rule out is kept after the digits around it are changed
+        let values: Vec<Vec<u32>> = self
+            .cells
+            .iter()
+            .zip(&self.board.solution)
+            .map(|(row, solution)| {
+                row.iter()
+                    .zip(solution)
+                    .map(|(cell, &digit)| if cell.value == digit { digit } else { 0 })
+                    .collect()
+            })
+            .collect();
+        let solver = HumanSolver::with_progress(&self.board, &values, &self.excluded);
+        if let Some(step) = solver.next_step() {
+            self.record(MoveKind::Hint, |game| {
+                for (cell, mask) in &step.eliminations {
+                    game.update(cell.row, cell.col, |cell, excluded| {
+                        *excluded |= mask;
+                        cell.notes &= !mask;
+                    });
+                }
+            });
+            let targets = step
+                .placements
+                .iter()
+                .map(|(cell, _)| *cell)
+                .chain(step.eliminations.iter().map(|(cell, _)| *cell))
+                .collect();
+            return Some(Hint {
+                text: step.explanation,
+                cells: step.cells,
+                targets,
+            });
+        }
+
+        let (r, c) = active
+            .filter(|&(r, c)| self.cells[r][c].value == 0)
+            .or_else(|| cells.clone().find(|&(r, c)| self.cells[r][c].value == 0))?;
+        let value = self.board.solution[r][c];
+        self.record(MoveKind::Hint, |game| game.place(r, c, value));
+        Some(Hint {
+            text: format!(
+                "No logical step found, so r{}c{} is revealed as {}",
+                r + 1,
+                c + 1,
+                value
+            ),
+            cells: vec![],
+            targets: vec![Cell::new(r, c)],
+        })
+    }
+
+    /// Places `value`, or takes it out if the cell already holds it, and
+    /// returns whether it matches the solution. A wrong digit counts as a
+    /// mistake only when checking immediately.
     pub fn set_value(&mut self, row: usize, col: usize, value: u32) -> bool {
-        self.push_state();
+        self.record(MoveKind::SetValue, |game| game.place(row, col, value))
+    }
+
+    fn place(&mut self, row: usize, col: usize, value: u32) -> bool {
         self.remove_notes(row, col, value);
-        self.cells[row][col].value = if self.cells[row][col].value == value {
-            0
-        } else {
-            value
-        };
-        self.cells[row][col].notes = 0;
-        self.board.solution[row][col] == value
+        self.update(row, col, |cell, _| {
+            cell.value = if cell.value == value { 0 } else { value };
+            cell.notes = 0;
+        });
+        if self.auto_notes {
+            self.sync_notes();
+            self.refill_notes(row, col);
+        }
+        let correct = self.board.solution[row][col] == value;
+        if !correct && self.cells[row][col].value != 0 && self.check_mode == CheckMode::Immediate {
+            self.mistakes = self.mistakes.saturating_add(1);
+        }
+        if self.finished.is_none() && self.cells_match_solution() {
+            self.pause();
+            self.finished = Some(self.played);
+        }
+        correct
     }
 
     pub fn clear_cell(&mut self, row: usize, col: usize) {
-        self.push_state();
-        self.cells[row][col].value = 0;
-        self.cells[row][col].notes = 0;
+        self.record(MoveKind::Clear, |game| {
+            game.update(row, col, |cell, _| *cell = GameCell::new());
+            if game.auto_notes {
+                game.sync_notes();
+                game.refill_notes(row, col);
+            }
+        });
     }
 
-    fn remove_notes(&mut self, r: usize, c: usize, v: u32) {
-        let br = r / 3;
-        let bc = c / 3;
-        for i in 0..3 {
-            for j in 0..3 {
-                self.cells[br + i][bc + j].clear_note(v as u8);
+    pub fn toggle_note(&mut self, row: usize, col: usize, value: u8) {
+        self.record(MoveKind::ToggleNote, |game| {
+            game.update(row, col, |cell, _| cell.toggle_note(value))
+        });
+    }
+
+    /// Rules the digits on the grid break, found without looking at the
+    /// solution: a digit repeated in a row, column, box or cage, a full cage
+    /// with the wrong total, or a cage whose digits leave less than one for
+    /// each of its empty cells.
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        let shape = self.board.shape;
+        let size = shape.size;
+        let mut groups: Vec<(ConflictKind, Vec<Cell>)> = Vec::new();
+        for i in 0..size {
+            let row = (0..size).map(|c| Cell::new(i, c)).collect();
+            let column = (0..size).map(|r| Cell::new(r, i)).collect();
+            let (br, bc) = shape.box_origin(i);
+            let cells = (0..shape.box_rows)
+                .flat_map(|r| (0..shape.box_cols).map(move |c| Cell::new(br + r, bc + c)))
+                .collect();
+            groups.push((ConflictKind::Row, row));
+            groups.push((ConflictKind::Column, column));
+            groups.push((ConflictKind::Box, cells));
+        }
+        for cage in &self.board.cages {
+            groups.push((ConflictKind::Cage, cage.cells.clone()));
+        }
+
+        let value = |cell: &Cell| self.cells[cell.row][cell.col].value;
+        let mut conflicts = Vec::new();
+        for (kind, cells) in groups {
+            for d in 1..=size as u32 {
+                let holding: Vec<Cell> = cells.iter().copied().filter(|c| value(c) == d).collect();
+                if holding.len() > 1 {
+                    conflicts.push(Conflict {
+                        kind,
+                        cells: holding,
+                    });
+                }
+            }
+        }
+        for cage in &self.board.cages {
+            let total: u32 = cage.cells.iter().map(value).sum();
+            let empty = cage.cells.iter().filter(|c| value(c) == 0).count() as u32;
+            if total + empty > cage.sum || (empty == 0 && total != cage.sum) {
+                conflicts.push(Conflict {
+                    kind: ConflictKind::CageSum,
+                    cells: cage.cells.clone(),
+                });
             }
         }
-        for i in 0..9 {
-            self.cells[r][i].clear_note(v as u8);
-            self.cells[i][c].clear_note(v as u8);
+        conflicts
+    }
+
+    fn cells_match_solution(&self) -> bool {
+        self.cells
+            .iter()
+            .zip(&self.board.solution)
+            .all(|(row, solution)| row.iter().zip(solution).all(|(cell, &d)| cell.value == d))
+    }
+
+    /// Whether every cell holds a digit.
+    pub fn is_full(&self) -> bool {
+        self.cells.iter().flatten().all(|cell| cell.value != 0)
+    }
+
+    /// The conflicts the check mode lets the player see. With checking
+    /// off they stay hidden until the grid is full.
+    pub fn visible_conflicts(&self) -> Vec<Conflict> {
+        if self.check_mode == CheckMode::Off && !self.is_full() {
+            return vec![];
         }
+        self.conflicts()
     }
 
-    pub fn push_state(&mut self) {
-        self.undo_state.push(self.cells.clone());
+    /// Cells whose digit is shown as wrong: those repeating a digit in a
+    /// house or cage, plus, when checking immediately or once a grid played
+    /// without checks is full, those not matching the solution.
+    pub fn flagged_cells(&self) -> Vec<Cell> {
+        let mut flagged: Vec<Cell> = self
+            .visible_conflicts()
+            .into_iter()
+            .filter(|conflict| conflict.kind != ConflictKind::CageSum)
+            .flat_map(|conflict| conflict.cells)
+            .collect();
+        let against_solution = match self.check_mode {
+            CheckMode::Immediate => true,
+            CheckMode::Conflicts => false,
+            CheckMode::Off => self.is_full(),
+        };
+        if against_solution {
+            for (r, row) in self.cells.iter().enumerate() {
+                for (c, cell) in row.iter().enumerate() {
+                    if cell.value != 0 && cell.value != self.board.solution[r][c] {
+                        flagged.push(Cell::new(r, c));
+                    }
+                }
+            }
+        }
+        flagged
     }
 
-    pub fn pop_state(&mut self) {
-        if let Some(last_state) = self.undo_state.pop() {
-            for r in 0..9 {
-                for c in 0..9 {
-                    self.cells[r][c].value = last_state[r][c].value;
-                    self.cells[r][c].notes = last_state[r][c].notes;
+    /// Digits each empty cell can still take: those not placed in its row,
+    /// column or box, not ruled out by a hint, and found in a combination
+    /// its cage can still make. Filled cells get none.
+    pub fn candidates(&self) -> Vec<Vec<u16>> {
+        let shape = self.board.shape;
+        let size = shape.size;
+        let mut used = vec![0u16; 3 * size];
+        for (r, row) in self.cells.iter().enumerate() {
+            for (c, cell) in row.iter().enumerate() {
+                if cell.value != 0 {
+                    used[r] |= 1 << cell.value;
+                    used[size + c] |= 1 << cell.value;
+                    used[2 * size + shape.box_index(r, c)] |= 1 << cell.value;
                 }
             }
         }
+        let all = combination_mask(1..=size as u32);
+        let mut candidates: Vec<Vec<u16>> = (0..size)
+            .map(|r| {
+                (0..size)
+                    .map(|c| {
+                        if self.cells[r][c].value != 0 {
+                            return 0;
+                        }
+                        let peers =
+                            used[r] | used[size + c] | used[2 * size + shape.box_index(r, c)];
+                        all & !peers & !self.excluded[r][c]
+                    })
+                    .collect()
+            })
+            .collect();
+        for cage in &self.board.cages {
+            let placed: Vec<u32> = cage
+                .cells
+                .iter()
+                .map(|cell| self.cells[cell.row][cell.col].value)
+                .collect();
+            let excluded: Vec<u16> = cage
+                .cells
+                .iter()
+                .map(|cell| all & !candidates[cell.row][cell.col])
+                .collect();
+            let combinations = cage.possible_combinations(shape, &placed, &excluded);
+            let allowed = combinations.iter().fold(0, |acc, c| acc | c) & !combination_mask(placed);
+            for cell in &cage.cells {
+                candidates[cell.row][cell.col] &= allowed;
+            }
+        }
+        candidates
+    }
+
+    /// Fills the notes of every empty cell with its candidates.
+    pub fn fill_candidates(&mut self) {
+        self.record(MoveKind::FillCandidates, Game::write_candidates);
+    }
+
+    /// Turns syncing notes with the candidates on or off. Turning it on
+    /// fills in the candidates right away.
+    pub fn toggle_auto_notes(&mut self) {
+        self.auto_notes = !self.auto_notes;
+        if self.auto_notes {
+            self.fill_candidates();
+        }
+    }
+
+    fn write_candidates(&mut self) {
+        for (r, row) in self.candidates().into_iter().enumerate() {
+            for (c, notes) in row.into_iter().enumerate() {
+                self.update(r, c, |cell, _| cell.notes = notes);
+            }
+        }
+    }
+
+    /// Drops the notes that are no longer candidates, leaving the notes a
+    /// player removed by hand out.
+    fn sync_notes(&mut self) {
+        for (r, row) in self.candidates().into_iter().enumerate() {
+            for (c, notes) in row.into_iter().enumerate() {
+                self.update(r, c, |cell, _| cell.notes &= notes);
+            }
+        }
+    }
+
+    /// Gives a cell its candidates as notes if it has just been emptied.
+    fn refill_notes(&mut self, row: usize, col: usize) {
+        if self.cells[row][col].value == 0 {
+            let notes = self.candidates()[row][col];
+            self.update(row, col, |cell, _| cell.notes = notes);
+        }
+    }
+
+    fn remove_notes(&mut self, r: usize, c: usize, v: u32) {
+        let shape = self.board.shape;
+        let (br, bc) = shape.box_origin(shape.box_index(r, c));
+        for i in 0..shape.box_rows {
+            for j in 0..shape.box_cols {
+                self.update(br + i, bc + j, |cell, _| cell.clear_note(v as u8));
+            }
+        }
+        for i in 0..shape.size {
+            self.update(r, i, |cell, _| cell.clear_note(v as u8));
+            self.update(i, c, |cell, _| cell.clear_note(v as u8));
+        }
+    }
+
+    /// Takes the last move back. Returns false when there is none.
+    pub fn undo(&mut self) -> bool {
+        self.history
+            .undo(&mut self.cells, &mut self.excluded)
+            .is_some()
+    }
+
+    /// Makes the last undone move again. Returns false when there is none.
+    pub fn redo(&mut self) -> bool {
+        self.history
+            .redo(&mut self.cells, &mut self.excluded)
+            .is_some()
+    }
+
+    /// Runs `change` and keeps the cells it changed through `update` as a
+    /// move.
+    fn record<T>(&mut self, kind: MoveKind, change: impl FnOnce(&mut Self) -> T) -> T {
+        let result = change(self);
+        let mut changes = std::mem::take(&mut self.changes);
+        changes.retain(|change| !change.is_unchanged());
+        if !changes.is_empty() {
+            self.history.record(Move { kind, changes });
+        }
+        result
+    }
+
+    /// Changes a cell and the digits hints ruled out in it, noting the
+    /// change for the move being recorded.
+    fn update(&mut self, row: usize, col: usize, change: impl FnOnce(&mut GameCell, &mut u16)) {
+        let (before, excluded_before) = (self.cells[row][col], self.excluded[row][col]);
+        change(&mut self.cells[row][col], &mut self.excluded[row][col]);
+        let (after, excluded_after) = (self.cells[row][col], self.excluded[row][col]);
+        if before == after && excluded_before == excluded_after {
+            return;
+        }
+        match self
+            .changes
+            .iter_mut()
+            .find(|change| change.row == row && change.col == col)
+        {
+            Some(change) => {
+                change.after = after;
+                change.excluded_after = excluded_after;
+            }
+            None => self.changes.push(CellChange {
+                row,
+                col,
+                before,
+                after,
+                excluded_before,
+                excluded_after,
+            }),
+        }
+    }
+}
+
+/// Points the player at a rule the grid breaks.
+fn conflict_hint(conflict: Conflict) -> Hint {
+    let names: Vec<String> = conflict
+        .cells
+        .iter()
+        .map(|cell| format!("r{}c{}", cell.row + 1, cell.col + 1))
+        .collect();
+    let house = match conflict.kind {
+        ConflictKind::Row => "row",
+        ConflictKind::Column => "column",
+        ConflictKind::Box => "box",
+        ConflictKind::Cage => "cage",
+        ConflictKind::CageSum => {
+            return Hint {
+                text: format!("The cage at {} can't add up to its sum", names[0]),
+                cells: vec![],
+                targets: conflict.cells,
+            }
+        }
+    };
+    Hint {
+        text: format!("{} repeat a digit in their {}", names.join(" and "), house),
+        cells: vec![],
+        targets: conflict.cells,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 4x4 board whose cage E runs across two rows, columns and boxes.
+    const BOARD: &str = "\
+[size]
+4 2x2
+[cages]
+ABBC
+ADEC
+FEEG
+FHHG
+[sums]
+A 4
+B 5
+C 6
+D 4
+E 8
+F 6
+G 4
+H 3
+[solution]
+1234
+3412
+2341
+4123
+";
+
+    /// A game on `BOARD` checking only the rules, with `digits` placed.
+    fn game_with(digits: &[(usize, usize, u32)]) -> Game {
+        let mut game = Game::new_with_board(SumdokuBoard::from_text(BOARD).unwrap());
+        game.check_mode = CheckMode::Conflicts;
+        for &(row, col, digit) in digits {
+            game.set_value(row, col, digit);
+        }
+        game
+    }
+
+    fn kinds(game: &Game) -> Vec<ConflictKind> {
+        game.conflicts()
+            .iter()
+            .map(|conflict| conflict.kind)
+            .collect()
+    }
+
+    #[test]
+    fn finds_a_digit_repeated_in_a_house_or_cage() {
+        let row = game_with(&[(0, 0, 1), (0, 3, 1)]);
+        assert_eq!(kinds(&row), vec![ConflictKind::Row]);
+        assert_eq!(row.flagged_cells(), vec![Cell::new(0, 0), Cell::new(0, 3)]);
+        let column = game_with(&[(0, 1, 2), (3, 1, 2)]);
+        assert_eq!(kinds(&column), vec![ConflictKind::Column]);
+        assert_eq!(
+            column.flagged_cells(),
+            vec![Cell::new(0, 1), Cell::new(3, 1)]
+        );
+        let in_box = game_with(&[(0, 1, 3), (1, 0, 3)]);
+        assert_eq!(kinds(&in_box), vec![ConflictKind::Box]);
+        assert_eq!(
+            in_box.flagged_cells(),
+            vec![Cell::new(0, 1), Cell::new(1, 0)]
+        );
+        let cage = game_with(&[(1, 2, 2), (2, 1, 2)]);
+        assert_eq!(kinds(&cage), vec![ConflictKind::Cage]);
+        assert_eq!(cage.flagged_cells(), vec![Cell::new(1, 2), Cell::new(2, 1)]);
+    }
+
+    #[test]
+    fn finds_a_full_cage_with_the_wrong_sum() {
+        let game = game_with(&[(0, 0, 1), (1, 0, 2)]);
+        let conflicts = game.conflicts();
+        assert_eq!(kinds(&game), vec![ConflictKind::CageSum]);
+        assert_eq!(conflicts[0].cells, vec![Cell::new(0, 0), Cell::new(1, 0)]);
+        // the cage is marked, not its digits
+        assert_eq!(game.flagged_cells(), vec![]);
+    }
+
+    #[test]
+    fn finds_a_partly_filled_cage_over_its_sum() {
+        // 3 leaves nothing for the other cell of a cage of 3
+        let game = game_with(&[(3, 1, 3)]);
+        assert_eq!(kinds(&game), vec![ConflictKind::CageSum]);
+        assert_eq!(
+            game.conflicts()[0].cells,
+            vec![Cell::new(3, 1), Cell::new(3, 2)]
+        );
+        assert_eq!(game.flagged_cells(), vec![]);
+    }
+
+    #[test]
+    fn finds_nothing_on_a_valid_partial_grid() {
+        let game = game_with(&[(0, 0, 1), (0, 1, 2), (1, 2, 1), (2, 1, 3), (3, 3, 3)]);
+        assert_eq!(kinds(&game), vec![]);
+        assert_eq!(game.flagged_cells(), vec![]);
+        // a digit that breaks no rule is only flagged against the solution
+        let mut game = game_with(&[(0, 0, 2)]);
+        assert_eq!(game.flagged_cells(), vec![]);
+        game.check_mode = CheckMode::Immediate;
+        assert_eq!(game.flagged_cells(), vec![Cell::new(0, 0)]);
+    }
+
+    #[test]
+    fn clock_leaves_out_paused_time() {
+        let pause = Duration::from_millis(20);
+        let mut game = Game::new_with_board(SumdokuBoard::from_text(BOARD).unwrap());
+        game.pause();
+        game.pause();
+        assert!(game.is_paused());
+        game.set_elapsed(Duration::from_secs(10));
+        std::thread::sleep(pause);
+        assert_eq!(game.elapsed(), Duration::from_secs(10));
+
+        game.resume();
+        std::thread::sleep(pause);
+        // resuming again keeps the time since the first resume
+        game.resume();
+        std::thread::sleep(pause);
+        game.pause();
+        let played = game.elapsed();
+        assert!(played >= Duration::from_secs(10) + 2 * pause);
+        assert!(played < Duration::from_secs(11));
+        std::thread::sleep(pause);
+        assert_eq!(game.elapsed(), played);
+
+        game.resume();
+        for (r, row) in game.board.solution.clone().iter().enumerate() {
+            for (c, &digit) in row.iter().enumerate() {
+                game.set_value(r, c, digit);
+            }
+        }
+        assert!(game.is_complete());
+        let finished = game.elapsed();
+        std::thread::sleep(pause);
+        game.pause();
+        game.resume();
+        assert!(!game.is_paused());
+        assert_eq!(game.elapsed(), finished);
     }
 
-    pub fn can_undo(self) -> bool {
-        self.undo_state.len() > 0
+    #[test]
+    fn hints_rule_out_nothing_from_a_wrong_digit() {
+        let board = SumdokuBoard::from_seed(1, Shape::new(2, 2), 4);
+        let digit = board.solution[0][0];
+        assert_ne!(digit, 1);
+        let mut game = Game::new_with_board(board);
+        game.check_mode = CheckMode::Conflicts;
+        // a wrong 1 that breaks no rule yet
+        game.set_value(0, 0, 1);
+        assert!(game.conflicts().is_empty());
+        assert!(game.hint(None).is_some());
+        game.clear_cell(0, 0);
+        assert_ne!(game.candidates()[0][0] & 1 << digit, 0);
     }
 }
@@ -0,0 +1,216 @@
+//! Moves made on the grid, kept as the cells they changed so that they can
+//! be undone and redone.
+
+use super::GameCell;
+
+/// What the player did to make a move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveKind {
+    SetValue,
+    Clear,
+    ToggleNote,
+    /// Digits ruled out, and their notes removed, or a digit revealed by a
+    /// hint.
+    Hint,
+    FillCandidates,
+}
+
+/// One cell as it was before and after a move.
+#[derive(Debug, Clone)]
+pub struct CellChange {
+    pub row: usize,
+    pub col: usize,
+    pub before: GameCell,
+    pub after: GameCell,
+    /// Digits hints had ruled out in the cell before the move.
+    pub excluded_before: u16,
+    pub excluded_after: u16,
+}
+
+impl CellChange {
+    /// Whether the move left the cell as it found it.
+    pub fn is_unchanged(&self) -> bool {
+        self.before == self.after && self.excluded_before == self.excluded_after
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Move {
+    pub kind: MoveKind,
+    pub changes: Vec<CellChange>,
+}
+
+/// Moves that can be undone, and those undone that can be made again.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    done: Vec<Move>,
+    undone: Vec<Move>,
+}
+
+impl History {
+    pub fn new(done: Vec<Move>, undone: Vec<Move>) -> Self {
+        History { done, undone }
+    }
+
+    /// Adds a move. Whatever was undone before it can't be redone anymore.
+    pub fn record(&mut self, change: Move) {
+        self.done.push(change);
+        self.undone.clear();
+    }
+
+    /// Takes the last move back on `cells` and the digits hints ruled out
+    /// in them, and says what it was.
+    pub fn undo(
+        &mut self,
+        cells: &mut [Vec<GameCell>],
+        excluded: &mut [Vec<u16>],
+    ) -> Option<MoveKind> {
+        let change = self.done.pop()?;
+        for cell in &change.changes {
+            cells[cell.row][cell.col] = cell.before;
+            excluded[cell.row][cell.col] = cell.excluded_before;
+        }
+        let kind = change.kind;
+        self.undone.push(change);
+        Some(kind)
+    }
+
+    /// Makes the last undone move again on `cells` and the digits hints
+    /// ruled out in them, and says what it was.
+    pub fn redo(
+        &mut self,
+        cells: &mut [Vec<GameCell>],
+        excluded: &mut [Vec<u16>],
+    ) -> Option<MoveKind> {
+        let change = self.undone.pop()?;
+        for cell in &change.changes {
+            cells[cell.row][cell.col] = cell.after;
+            excluded[cell.row][cell.col] = cell.excluded_after;
+        }
+        let kind = change.kind;
+        self.done.push(change);
+        Some(kind)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Moves that can be undone, oldest first.
+    pub fn done(&self) -> &[Move] {
+        &self.done
+    }
+
+    /// Moves that can be redone, the next one last.
+    pub fn undone(&self) -> &[Move] {
+        &self.undone
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::logic::{Shape, SumdokuBoard};
+
+    fn game() -> Game {
+        Game::new_with_board(SumdokuBoard::from_seed(1, Shape::CLASSIC, 6))
+    }
+
+    fn last_kind(game: &Game) -> Option<MoveKind> {
+        game.history.done().last().map(|change| change.kind)
+    }
+
+    #[test]
+    fn undoes_and_redoes_a_digit() {
+        let mut game = game();
+        game.toggle_note(0, 1, 5);
+        game.set_value(0, 0, 5);
+        assert_eq!(last_kind(&game), Some(MoveKind::SetValue));
+        assert_eq!(game.cells[0][1].notes, 0);
+        assert!(game.undo());
+        assert_eq!(game.cells[0][0].value, 0);
+        assert_eq!(game.cells[0][1].notes, 1 << 5);
+        assert!(game.redo());
+        assert_eq!(game.cells[0][0].value, 5);
+        assert_eq!(game.cells[0][1].notes, 0);
+    }
+
+    #[test]
+    fn undoes_and_redoes_a_clear() {
+        let mut game = game();
+        game.set_value(4, 4, 3);
+        game.clear_cell(4, 4);
+        assert_eq!(last_kind(&game), Some(MoveKind::Clear));
+        assert!(game.undo());
+        assert_eq!(game.cells[4][4].value, 3);
+        assert!(game.redo());
+        assert_eq!(game.cells[4][4].value, 0);
+    }
+
+    #[test]
+    fn undoes_and_redoes_a_note() {
+        let mut game = game();
+        game.toggle_note(2, 3, 7);
+        assert_eq!(last_kind(&game), Some(MoveKind::ToggleNote));
+        assert!(game.undo());
+        assert_eq!(game.cells[2][3].notes, 0);
+        assert!(game.redo());
+        assert_eq!(game.cells[2][3].notes, 1 << 7);
+    }
+
+    #[test]
+    fn undoes_and_redoes_filled_candidates() {
+        let mut game = game();
+        game.fill_candidates();
+        assert_eq!(last_kind(&game), Some(MoveKind::FillCandidates));
+        let filled = game.cells.clone();
+        assert!(filled.iter().flatten().any(|cell| cell.notes != 0));
+        assert!(game.undo());
+        assert!(game.cells.iter().flatten().all(|cell| cell.notes == 0));
+        assert!(game.redo());
+        assert_eq!(game.cells, filled);
+    }
+
+    #[test]
+    fn undoes_and_redoes_the_digits_a_hint_rules_out() {
+        let mut game = game();
+        // follow the hints until one rules digits out rather than placing one
+        let mut excluded = None;
+        for _ in 0..81 {
+            let before = game.excluded.clone();
+            let hint = game.hint(None).unwrap();
+            if game.excluded != before {
+                assert_eq!(last_kind(&game), Some(MoveKind::Hint));
+                excluded = Some((before, game.excluded.clone()));
+                break;
+            }
+            for cell in hint.targets {
+                if game.cells[cell.row][cell.col].value == 0 {
+                    let digit = game.board.solution[cell.row][cell.col];
+                    game.set_value(cell.row, cell.col, digit);
+                }
+            }
+        }
+        let (before, after) = excluded.expect("no hint ruled out a digit");
+        assert!(game.undo());
+        assert_eq!(game.excluded, before);
+        assert!(game.redo());
+        assert_eq!(game.excluded, after);
+    }
+
+    #[test]
+    fn a_new_move_drops_the_undone_ones() {
+        let mut game = game();
+        game.set_value(0, 0, 1);
+        assert!(game.undo());
+        assert!(game.history.can_redo());
+        game.set_value(0, 0, 2);
+        assert!(!game.history.can_redo());
+        assert!(!game.redo());
+    }
+}
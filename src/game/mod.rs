@@ -1,3 +1,6 @@
 pub mod game;
+pub mod history;
+pub mod save;
 
-pub use game::*;
\ No newline at end of file
+pub use game::*;
+pub use history::*;
@@ -0,0 +1,351 @@
+//! Saving a game in progress so it can be resumed after the window closes.
+//!
+//! The game is written as JSON to `save.json` in a per-user data directory:
+//! `$XDG_DATA_HOME/sumdoku` (or `~/.local/share/sumdoku`) on Linux,
+//! `~/Library/Application Support/sumdoku` on macOS and `%APPDATA%\sumdoku`
+//! on Windows. The board is kept in the plain text format, the clock as the
+//! time played so far.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde_json::{json, Value};
+
+use super::{CellChange, CheckMode, Game, GameCell, History, Move, MoveKind};
+use crate::logic::SumdokuBoard;
+
+const SAVE_FILE: &str = "save.json";
+
+impl Game {
+    pub fn to_json(&self) -> String {
+        let finished = self.finished.map(|time| time.as_millis() as u64);
+        json!({
+            "board": self.board.to_text(),
+            "cells": cells_json(&self.cells),
+            "elapsed": self.elapsed().as_millis() as u64,
+            "finished": finished,
+            "mistakes": self.mistakes,
+            "hints": self.hints,
+            "auto_notes": self.auto_notes,
+            "check_mode": check_mode_name(self.check_mode),
+            "excluded": self.excluded,
+            "done": moves_json(self.history.done()),
+            "undone": moves_json(self.history.undone()),
+        })
+        .to_string()
+    }
+
+    /// Reads a game written by `to_json`. Its clock starts out paused.
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        let json: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        let board = json
+            .get("board")
+            .and_then(Value::as_str)
+            .ok_or("missing board")?;
+        let board = SumdokuBoard::from_text(board)?;
+        let size = board.shape.size;
+        let mut game = Game::new_with_board(board);
+        game.cells = parse_cells(json.get("cells").ok_or("missing cells")?, size)?;
+        // the clock stays stopped until the player picks the game up again
+        game.pause();
+        game.set_elapsed(Duration::from_millis(number(&json, "elapsed")?));
+        game.finished = match json.get("finished") {
+            None | Some(Value::Null) => None,
+            Some(_) => Some(Duration::from_millis(number(&json, "finished")?)),
+        };
+        game.mistakes = number(&json, "mistakes")?.min(u8::MAX as u64) as u8;
+        game.hints = number(&json, "hints")? as u32;
+        game.auto_notes = json
+            .get("auto_notes")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        game.check_mode = match json.get("check_mode").and_then(Value::as_str) {
+            Some("conflicts") => CheckMode::Conflicts,
+            Some("off") => CheckMode::Off,
+            _ => CheckMode::Immediate,
+        };
+        if let Some(excluded) = json.get("excluded") {
+            game.excluded = parse_grid(excluded, size, |v| v.as_u64().map(|v| v as u16))?;
+        }
+        game.history = History::new(
+            parse_moves(json.get("done"), size)?,
+            parse_moves(json.get("undone"), size)?,
+        );
+        Ok(game)
+    }
+}
+
+/// Where games are saved, or `None` when the platform gives no home for
+/// user data.
+pub fn save_path() -> Option<PathBuf> {
+    Some(user_dir("XDG_DATA_HOME", ".local/share")?.join(SAVE_FILE))
+}
+
+/// The game's own directory in a per-user base directory: on Linux the one
+/// named by the `xdg_variable` environment variable, or else `xdg_default`
+/// under the home directory, `~/Library/Application Support` on macOS and
+/// `%APPDATA%` on Windows. `None` when the platform gives no such base.
+pub fn user_dir(xdg_variable: &str, xdg_default: &str) -> Option<PathBuf> {
+    let env = |name| std::env::var_os(name).map(PathBuf::from);
+    let dir = if cfg!(windows) {
+        env("APPDATA")?
+    } else if cfg!(target_os = "macos") {
+        env("HOME")?.join("Library/Application Support")
+    } else {
+        env(xdg_variable).or_else(|| Some(env("HOME")?.join(xdg_default)))?
+    };
+    Some(dir.join("sumdoku"))
+}
+
+/// Writes `text` to `path`, making its directory if need be. It is written
+/// aside first so that a crash never leaves half a file behind.
+pub fn write_atomically(path: &Path, text: &str) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    fs::write(&temporary, text).map_err(|e| e.to_string())?;
+    fs::rename(&temporary, path).map_err(|e| e.to_string())
+}
+
+/// Saves the game, or drops the save once the game is solved, since there
+/// is nothing left to resume.
+pub fn autosave(game: &Game) -> Result<(), String> {
+    let path = save_path().ok_or("no data directory")?;
+    if game.is_complete() {
+        return match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+            _ => Ok(()),
+        };
+    }
+    write_atomically(&path, &game.to_json())
+}
+
+/// Loads the saved game, if there is one.
+pub fn load() -> Result<Option<Game>, String> {
+    let Some(path) = save_path() else {
+        return Ok(None);
+    };
+    match fs::read_to_string(&path) {
+        Ok(text) => Game::from_json(&text).map(Some),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn check_mode_name(mode: CheckMode) -> &'static str {
+    match mode {
+        CheckMode::Immediate => "immediate",
+        CheckMode::Conflicts => "conflicts",
+        CheckMode::Off => "off",
+    }
+}
+
+const MOVE_KINDS: [(MoveKind, &str); 5] = [
+    (MoveKind::SetValue, "set_value"),
+    (MoveKind::Clear, "clear"),
+    (MoveKind::ToggleNote, "toggle_note"),
+    (MoveKind::Hint, "hint"),
+    (MoveKind::FillCandidates, "fill_candidates"),
+];
+
+/// Moves as their kind and a list of changes, each written as
+/// `[row, col, value before, notes before, value after, notes after,
+/// excluded before, excluded after]`.
+fn moves_json(moves: &[Move]) -> Value {
+    let moves: Vec<Value> = moves
+        .iter()
+        .map(|change| {
+            let kind = MOVE_KINDS.iter().find(|(kind, _)| *kind == change.kind);
+            let changes: Vec<[u32; 8]> = change
+                .changes
+                .iter()
+                .map(|c| {
+                    [
+                        c.row as u32,
+                        c.col as u32,
+                        c.before.value,
+                        c.before.notes as u32,
+                        c.after.value,
+                        c.after.notes as u32,
+                        c.excluded_before as u32,
+                        c.excluded_after as u32,
+                    ]
+                })
+                .collect();
+            json!({ "kind": kind.map(|(_, name)| *name), "changes": changes })
+        })
+        .collect();
+    json!(moves)
+}
+
+fn parse_moves(json: Option<&Value>, size: usize) -> Result<Vec<Move>, String> {
+    let Some(json) = json else {
+        return Ok(vec![]);
+    };
+    let moves = json.as_array().ok_or("moves must be a list")?;
+    moves
+        .iter()
+        .map(|entry| {
+            let name = entry.get("kind").and_then(Value::as_str);
+            let kind = MOVE_KINDS
+                .iter()
+                .find(|(_, n)| Some(*n) == name)
+                .map(|(kind, _)| *kind)
+                .ok_or(format!("invalid move {}", entry))?;
+            let changes = entry.get("changes").and_then(Value::as_array);
+            let changes = changes
+                .ok_or(format!("invalid move {}", entry))?
+                .iter()
+                .map(|change| {
+                    parse_change(change, size).ok_or(format!("invalid change {}", change))
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(Move { kind, changes })
+        })
+        .collect()
+}
+
+fn parse_change(json: &Value, size: usize) -> Option<CellChange> {
+    let fields = json.as_array().filter(|fields| fields.len() == 8)?;
+    let fields: Vec<u64> = fields.iter().map(Value::as_u64).collect::<Option<_>>()?;
+    let cell = |value: u64, notes: u64| {
+        (value <= size as u64).then_some(GameCell {
+            value: value as u32,
+            notes: notes as u16,
+        })
+    };
+    if fields[0] >= size as u64 || fields[1] >= size as u64 {
+        return None;
+    }
+    Some(CellChange {
+        row: fields[0] as usize,
+        col: fields[1] as usize,
+        before: cell(fields[2], fields[3])?,
+        after: cell(fields[4], fields[5])?,
+        excluded_before: fields[6] as u16,
+        excluded_after: fields[7] as u16,
+    })
+}
+
+/// Cells as rows of `[value, notes]` pairs.
+fn cells_json(cells: &[Vec<GameCell>]) -> Value {
+    let rows: Vec<Vec<[u32; 2]>> = cells
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| [cell.value, cell.notes as u32])
+                .collect()
+        })
+        .collect();
+    json!(rows)
+}
+
+fn parse_cells(json: &Value, size: usize) -> Result<Vec<Vec<GameCell>>, String> {
+    parse_grid(json, size, |cell| {
+        let pair = cell.as_array().filter(|pair| pair.len() == 2)?;
+        let value = pair[0].as_u64().filter(|&v| v <= size as u64)?;
+        let notes = pair[1].as_u64()?;
+        Some(GameCell {
+            value: value as u32,
+            notes: notes as u16,
+        })
+    })
+}
+
+/// Reads a `size` by `size` grid, one JSON value per cell.
+fn parse_grid<T>(
+    json: &Value,
+    size: usize,
+    parse: impl Fn(&Value) -> Option<T>,
+) -> Result<Vec<Vec<T>>, String> {
+    let rows = json.as_array().filter(|rows| rows.len() == size);
+    let rows = rows.ok_or(format!("expected {} rows", size))?;
+    rows.iter()
+        .map(|row| {
+            let row = row.as_array().filter(|row| row.len() == size);
+            let row = row.ok_or(format!("expected {} cells per row", size))?;
+            row.iter()
+                .map(|cell| parse(cell).ok_or(format!("invalid cell {}", cell)))
+                .collect()
+        })
+        .collect()
+}
+
+fn number(json: &Value, key: &str) -> Result<u64, String> {
+    json.get(key)
+        .and_then(Value::as_u64)
+        .ok_or(format!("missing {}", key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::Shape;
+
+    fn game() -> Game {
+        let mut game = Game::new_with_board(SumdokuBoard::from_seed(2, Shape::CLASSIC, 6));
+        game.check_mode = CheckMode::Off;
+        game.set_value(0, 0, 4);
+        game.toggle_note(0, 1, 3);
+        game.toggle_note(0, 1, 7);
+        game.set_value(8, 8, 2);
+        game.undo();
+        game.mistakes = 3;
+        game.pause();
+        game.set_elapsed(Duration::from_secs(95));
+        game
+    }
+
+    #[test]
+    fn round_trips_a_game_in_progress() {
+        let game = game();
+        let text = game.to_json();
+        let mut loaded = Game::from_json(&text).unwrap();
+        assert_eq!(loaded.board.to_text(), game.board.to_text());
+        assert_eq!(loaded.cells, game.cells);
+        assert_eq!(loaded.cells[0][0].value, 4);
+        assert_eq!(loaded.cells[0][1].notes, 1 << 3 | 1 << 7);
+        assert_eq!(loaded.elapsed(), Duration::from_secs(95));
+        assert!(loaded.is_paused());
+        assert_eq!(loaded.mistakes, 3);
+        assert_eq!(loaded.check_mode, CheckMode::Off);
+        assert_eq!(loaded.to_json(), text);
+        // the undone digit comes back, and the moves before it undo in order
+        assert!(loaded.redo());
+        assert_eq!(loaded.cells[8][8].value, 2);
+        assert!(loaded.undo());
+        assert!(loaded.undo());
+        assert!(loaded.undo());
+        assert_eq!(loaded.cells[0][1].notes, 0);
+        assert!(loaded.undo());
+        assert_eq!(loaded.cells[0][0].value, 0);
+        assert!(!loaded.undo());
+    }
+
+    #[test]
+    fn rejects_malformed_saves() {
+        assert!(Game::from_json("{ \"board\": ").is_err());
+        assert_eq!(
+            Game::from_json("{}").err(),
+            Some("missing board".to_string())
+        );
+        let mut json: Value = serde_json::from_str(&game().to_json()).unwrap();
+        json["cells"] = json!([[0, 0]]);
+        assert_eq!(
+            Game::from_json(&json.to_string()).err(),
+            Some("expected 9 rows".to_string())
+        );
+        // every change has to carry the exclusions before and after it
+        let mut json: Value = serde_json::from_str(&game().to_json()).unwrap();
+        json["done"][0]["changes"] = json!([[0, 0, 0, 0, 4, 0]]);
+        assert_eq!(
+            Game::from_json(&json.to_string()).err(),
+            Some("invalid change [0,0,0,0,4,0]".to_string())
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! Fonts, icons and rendered text, loaded the first time they are needed
+//! and kept for the frames after.
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use sdl2::{
+    image::LoadTexture,
+    pixels::Color,
+    render::{Texture, TextureCreator},
+    ttf::{Font, Sdl2TtfContext},
+    video::WindowContext,
+};
+
+const FONT_PATH: &str = "./assets/NotoSans-Regular.ttf";
+const BOLD_FONT_PATH: &str = "./assets/NotoSans-SemiBold.ttf";
+/// Rendered text is thrown away once this much of it is kept, so that
+/// labels that keep changing, like the clock, don't pile up.
+const MAX_TEXTS: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Weight {
+    Regular,
+    Bold,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct TextKey {
+    text: String,
+    weight: Weight,
+    size: u16,
+    color: Color,
+}
+
+pub struct Assets<'a> {
+    ttf_context: &'a Sdl2TtfContext,
+    texture_creator: &'a TextureCreator<WindowContext>,
+    fonts: RefCell<HashMap<(Weight, u16), Rc<Font<'a, 'static>>>>,
+    icons: RefCell<HashMap<&'static str, Rc<Texture<'a>>>>,
+    texts: RefCell<HashMap<TextKey, Rc<Texture<'a>>>>,
+}
+
+impl<'a> Assets<'a> {
+    pub fn new(
+        ttf_context: &'a Sdl2TtfContext,
+        texture_creator: &'a TextureCreator<WindowContext>,
+    ) -> Self {
+        Self {
+            ttf_context,
+            texture_creator,
+            fonts: RefCell::new(HashMap::new()),
+            icons: RefCell::new(HashMap::new()),
+            texts: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn font(&self, weight: Weight, size: u16) -> Result<Rc<Font<'a, 'static>>, String> {
+        if let Some(font) = self.fonts.borrow().get(&(weight, size)) {
+            return Ok(font.clone());
+        }
+        let path = match weight {
+            Weight::Regular => FONT_PATH,
+            Weight::Bold => BOLD_FONT_PATH,
+        };
+        // SDL_ttf can't open a font at size 0
+        let font = Rc::new(self.ttf_context.load_font(path, size.max(1))?);
+        self.fonts.borrow_mut().insert((weight, size), font.clone());
+        Ok(font)
+    }
+
+    /// The image at `path` as a texture.
+    pub fn icon(&self, path: &'static str) -> Result<Rc<Texture<'a>>, String> {
+        if let Some(icon) = self.icons.borrow().get(path) {
+            return Ok(icon.clone());
+        }
+        let icon = Rc::new(self.texture_creator.load_texture(path)?);
+        self.icons.borrow_mut().insert(path, icon.clone());
+        Ok(icon)
+    }
+
+    /// `text` rendered as a texture of its own size.
+    pub fn text(
+        &self,
+        text: &str,
+        weight: Weight,
+        size: u16,
+        color: Color,
+    ) -> Result<Rc<Texture<'a>>, String> {
+        let key = TextKey {
+            text: text.to_string(),
+            weight,
+            size,
+            color,
+        };
+        if let Some(texture) = self.texts.borrow().get(&key) {
+            return Ok(texture.clone());
+        }
+        let surface = self
+            .font(weight, size)?
+            .render(text)
+            .blended(color)
+            .map_err(|e| e.to_string())?;
+        let texture = self
+            .texture_creator
+            .create_texture_from_surface(&surface)
+            .map_err(|e| e.to_string())?;
+        let texture = Rc::new(texture);
+        let mut texts = self.texts.borrow_mut();
+        if texts.len() >= MAX_TEXTS {
+            texts.clear();
+        }
+        texts.insert(key, texture.clone());
+        Ok(texture)
+    }
+
+    /// Drops the text rendered so far, for when the sizes it was drawn at
+    /// are no longer used.
+    pub fn clear_texts(&self) {
+        self.texts.borrow_mut().clear();
+    }
+}
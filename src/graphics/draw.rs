@@ -1,106 +1,315 @@
-use std::cell::{RefCell, RefMut};
+use std::{
+    cell::{RefCell, RefMut},
+    time::Duration,
+};
 
 use sdl2::{
-    image::LoadTexture, keyboard::Keycode, pixels::Color, rect::{Point, Rect}, render::{TextureCreator, WindowCanvas}, ttf::{self}, video::WindowContext
+    keyboard::Keycode, pixels::Color, render::BlendMode, rect::{Point, Rect}, render::{Texture, WindowCanvas}
 };
 
-use crate::{game::Game, logic::Cage};
+use super::{Assets, Layout, Menu, MenuItem, Screen, Theme, Themes, Weight};
+use crate::{game::{save, CheckMode, ConflictKind, Game, Hint, Options}, logic::{Cage, Cell}};
 
-const FONT_PATH: &str = "./assets/NotoSans-Regular.ttf";
-const BOLD_FONT_PATH: &str = "./assets/NotoSans-SemiBold.ttf";
 const UNDO_ICON: &str = "./assets/undo.png";
 const ERASE_ICON: &str = "./assets/erase.png";
 const NOTE_ICON: &str = "./assets/note.png";
 const HINT_ICON: &str = "./assets/hint.png";
+const MENU_ROW_HEIGHT: i32 = 70;
 
 
-pub struct GameRenderer {
+pub struct GameRenderer<'a> {
     canvas: RefCell<WindowCanvas>,
     game: RefCell<Game>,
-    cell_size: i32,
-    cage_offset: i32,
-    notes_gap: i32,
-    grid_color: Color,
-    cage_color: Color,
-    active_cell_background: Color,
-    highlight_color: Color,
+    assets: Assets<'a>,
+    layout: Layout,
+    /// Pixels drawn per point of the window, for mapping mouse positions
+    /// onto high DPI displays.
+    pixel_ratio: f32,
+    themes: Themes,
+    /// Set once the player picks another theme, so that it gets saved.
+    theme_changed: bool,
     notes_mode: bool,
     active_cell: Option<(usize, usize)>,
     active_number: u32,
-    board_position: Point,
+    hint: Option<Hint>,
+    /// A saved game the player has been offered to resume.
+    saved: Option<Game>,
+    screen: Screen,
+    menu: Menu,
+    /// Whether the game was already paused when the menu opened, so that
+    /// closing it leaves the game paused.
+    paused_before_menu: bool,
+    quit: bool,
+    /// Set when something changed since the last frame.
+    dirty: bool,
+    /// The second the clock showed on the last frame.
+    shown_second: u64,
 }
 
-impl GameRenderer {
-    pub fn new(canvas: RefCell<WindowCanvas>, game: RefCell<Game>, cell_size: u32) -> Self {
-        Self {
+impl<'a> GameRenderer<'a> {
+    pub fn new(
+        canvas: RefCell<WindowCanvas>,
+        game: RefCell<Game>,
+        assets: Assets<'a>,
+        themes: Themes,
+    ) -> Self {
+        let shape = game.borrow().board.shape;
+        let theme = themes.current().name.clone();
+        let (width, height) = canvas.borrow().window().size();
+        let mut renderer = Self {
             canvas,
             game,
-            cell_size: cell_size as i32,
-            cage_offset: 5,
-            notes_gap: 3,
-            grid_color: Color::BLACK,
-            cage_color: Color::BLUE,
-            active_cell_background: Color::RGB(172, 200, 229),
-            highlight_color: Color::RGB(200, 208, 222),
+            assets,
+            layout: Layout::new(width, height, shape),
+            pixel_ratio: 1.0,
+            themes,
+            theme_changed: false,
             notes_mode: false,
             active_cell: None,
             active_number: 0,
-            board_position: Point::new(1, 40),
+            hint: None,
+            saved: None,
+            screen: Screen::Board,
+            menu: Menu::new(Options::new(shape), theme),
+            paused_before_menu: false,
+            quit: false,
+            dirty: true,
+            shown_second: 0,
+        };
+        renderer.resize();
+        renderer
+    }
+
+    /// Asks the player whether to carry on with `game` instead.
+    pub fn offer_resume(&mut self, game: Game) {
+        self.game_mut().pause();
+        self.saved = Some(game);
+        self.dirty = true;
+    }
+
+    /// Stops the clock and hides the board until the player comes back.
+    pub fn pause(&mut self) {
+        self.game_mut().pause();
+        self.dirty = true;
+    }
+
+    /// Has the whole window drawn again, as when it was uncovered.
+    pub fn redraw(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Writes the theme to the config file if the player picked another.
+    pub fn save_theme(&self) -> Result<(), String> {
+        if !self.theme_changed {
+            return Ok(());
+        }
+        self.themes.save_choice()
+    }
+
+    /// Lays the window out again for its current size.
+    pub fn resize(&mut self) {
+        let canvas = self.canvas.borrow();
+        let (width, _) = canvas.window().size();
+        let (pixels_wide, pixels_high) = canvas
+            .output_size()
+            .unwrap_or_else(|_| canvas.window().size());
+        drop(canvas);
+        self.pixel_ratio = pixels_wide as f32 / width.max(1) as f32;
+        let shape = self.game.borrow().board.shape;
+        self.layout = Layout::new(pixels_wide, pixels_high, shape);
+        // cage outlines and text are drawn at the old sizes
+        for cage in self.game_mut().board.cages.iter_mut() {
+            cage.lines = None;
+        }
+        self.assets.clear_texts();
+        self.dirty = true;
+    }
+
+    /// Whether the window shows something out of date: it changed since
+    /// the last frame, or the clock moved on to another second.
+    pub fn needs_redraw(&self) -> bool {
+        self.dirty || self.game.borrow().elapsed().as_secs() != self.shown_second
+    }
+
+    /// How long until the clock shows the next second, or `None` while it
+    /// is stopped.
+    pub fn until_tick(&self) -> Option<Duration> {
+        let game = self.game.borrow();
+        if game.is_paused() || game.is_complete() {
+            return None;
+        }
+        let elapsed = game.elapsed();
+        Some(Duration::from_secs(elapsed.as_secs() + 1) - elapsed)
+    }
+
+    /// Whether the player chose to quit from the menu.
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    /// Saves the game being played, or the one still offered for resuming.
+    pub fn autosave(&self) -> Result<(), String> {
+        match &self.saved {
+            Some(game) => save::autosave(game),
+            None => save::autosave(&self.game.borrow()),
         }
     }
 
-    pub fn render(&self) -> Result<(), String> {
-        self.set_color(&Color::WHITE);
+    pub fn render(&mut self) -> Result<(), String> {
+        self.dirty = false;
+        self.shown_second = self.game.borrow().elapsed().as_secs();
+        self.set_color(&self.theme().background);
         self.canvas_mut().clear();
+        if self.screen == Screen::Menu {
+            self.draw_menu()?;
+            self.canvas_mut().present();
+            return Ok(());
+        }
         self.draw_status()?;
-        self.draw_board()?;
+        if self.game.borrow().is_paused() && self.saved.is_none() {
+            self.draw_paused()?;
+        } else {
+            self.draw_board()?;
+            self.draw_hint()?;
+        }
         self.draw_buttons()?;
         self.draw_number_picker()?;
+        self.draw_results()?;
+        self.draw_resume_prompt()?;
         self.canvas_mut().present();
         Ok(())
     }
 
+    /// Acts on a click at `x`, `y` in window points.
     pub fn handle_click(&mut self, x: i32, y: i32) {
-        let board_size = self.cell_size * 9;
-        if self.between(x, self.board_position.x, self.board_position.x + board_size)
-            && self.between(y, self.board_position.y, self.board_position.y + board_size)
-        {
-            let (r, c) = self.get_cell(x, y);
-            self.active_cell = Some((r, c));
+        self.dirty = true;
+        let x = (x as f32 * self.pixel_ratio) as i32;
+        let y = (y as f32 * self.pixel_ratio) as i32;
+        if self.screen == Screen::Menu {
+            let row_height = self.layout.scaled(MENU_ROW_HEIGHT);
+            let row = (y - self.menu_top()) / row_height;
+            if y >= self.menu_top() && (row as usize) < MenuItem::ALL.len() {
+                self.menu.selected = row as usize;
+                self.activate(MenuItem::ALL[row as usize]);
+            }
+            return;
+        }
+        if self.saved.is_some() {
+            let (resume, new_game) = self.overlay_buttons();
+            if resume.contains_point((x, y)) {
+                self.resume();
+            } else if new_game.contains_point((x, y)) {
+                self.discard_saved();
+            }
+            return;
+        }
+        let status = self.layout.status;
+        let status_third = status.width() as i32 / 3;
+        if status.contains_point((x, y)) && x > status.right() - status_third {
+            self.toggle_pause();
+            return;
+        }
+        let shape = self.game.borrow().board.shape;
+        let cell = self.layout.cell_at(shape, x, y);
+        if self.game.borrow().is_paused() {
+            if cell.is_some() {
+                self.toggle_pause();
+            }
+            return;
+        }
+        if self.game.borrow().is_complete() {
+            let (new_game, replay) = self.overlay_buttons();
+            if new_game.contains_point((x, y)) {
+                self.new_game();
+            } else if replay.contains_point((x, y)) {
+                self.replay();
+            }
+            return;
+        }
+        if cell.is_some() {
+            self.active_cell = cell;
             return;
         }
-        let picker_y = 100 + self.board_position.y + (9 * self.cell_size);
-        if self.between(x, self.board_position.x, self.board_position.x + board_size)
-            && self.between(y, picker_y, picker_y + self.cell_size)
-        {
+        if let Some(key) = self.layout.key_at(shape, x, y) {
             if let Some((r, c)) = self.active_cell {
+                self.hint = None;
                 let mut game = self.game_mut();
-                let number = (self.get_col(x) + 1) as u32;
+                let number = (key + 1) as u32;
                 if self.notes_mode {
-                    game.cells[r][c].toggle_note(number as u8);
-                } else if !game.set_value(r, c, number) {
-                    game.mistakes += 1;
+                    game.toggle_note(r, c, number as u8);
+                } else {
+                    game.set_value(r, c, number);
                 }
             }
             return;
         }
-        let button_y = 9 * self.cell_size + self.board_position.y + 20;
-        if self.between(y, button_y, button_y + 60) {
-            match self.get_button(x) {
-                1 => self.undo(),
-                2 => self.clear(),
-                3 => self.toggle_notes_mode(),
-                4 => self.hint(),
-                _ => {}
-            };
+        if status.contains_point((x, y)) && x < status.x() + status_third {
+            self.cycle_check_mode();
+            return;
+        }
+        match self.layout.button_at(x, y) {
+            Some(0) => self.undo(),
+            Some(1) => self.redo(),
+            Some(2) => self.clear(),
+            Some(3) => self.toggle_notes_mode(),
+            Some(4) => self.hint(),
+            _ => {}
         }
     }
 
     pub fn handle_keyboard_input(&mut self, key: Keycode) {
+        self.dirty = true;
+        if self.screen == Screen::Menu {
+            match key {
+                Keycode::Escape => self.close_menu(),
+                Keycode::Up => self.menu.select(-1),
+                Keycode::Down => self.menu.select(1),
+                Keycode::Left => self.change_option(self.menu.selected_item(), -1),
+                Keycode::Right => self.change_option(self.menu.selected_item(), 1),
+                Keycode::Return | Keycode::KP_ENTER => self.activate(self.menu.selected_item()),
+                _ => {}
+            }
+            return;
+        }
+        if key == Keycode::Escape && self.saved.is_none() {
+            self.menu.selected = 0;
+            self.screen = Screen::Menu;
+            self.paused_before_menu = self.game.borrow().is_paused();
+            self.pause();
+            return;
+        }
+        if self.saved.is_some() {
+            match key {
+                Keycode::R => self.resume(),
+                Keycode::N => self.discard_saved(),
+                _ => {}
+            }
+            return;
+        }
+        if key == Keycode::P {
+            self.toggle_pause();
+            return;
+        }
+        if self.game.borrow().is_paused() {
+            return;
+        }
+        if self.game.borrow().is_complete() {
+            match key {
+                Keycode::N => self.new_game(),
+                Keycode::R => self.replay(),
+                _ => {}
+            }
+            return;
+        }
         match key {
             Keycode::N => self.toggle_notes_mode(),
             Keycode::E => self.clear(),
             Keycode::U => self.undo(),
+            Keycode::R => self.redo(),
+            Keycode::C => self.fill_candidates(),
+            Keycode::A => self.toggle_auto_notes(),
+            Keycode::M => self.cycle_check_mode(),
+            Keycode::T => self.cycle_theme(1),
             Keycode::Up => self.move_to(-1, 0),
             Keycode::Down => self.move_to(1, 0),
             Keycode::Left => self.move_to(0,-1),
@@ -114,14 +323,19 @@ impl GameRenderer {
             Keycode::Num7 | Keycode::KP_7 => self.try_set(7),
             Keycode::Num8 | Keycode::KP_8 => self.try_set(8),
             Keycode::Num9 | Keycode::KP_9 => self.try_set(9),
+            // on a 12x12 grid the keys after 9 on the top row stand for 10-12
+            Keycode::Num0 | Keycode::KP_0 => self.try_set(10),
+            Keycode::Minus | Keycode::KP_MINUS => self.try_set(11),
+            Keycode::Equals | Keycode::KP_PLUS => self.try_set(12),
             _ => {}
         }
     }
 
     fn move_to(&mut self, dr: i8, dc: i8) {
         if let Some((r,c)) = self.active_cell {
-            let nr = (r as i8 + dr).max(0).min(8) as usize;
-            let nc = (c as i8 + dc).max(0).min(8) as usize;
+            let last = self.size() as i8 - 1;
+            let nr = (r as i8 + dr).clamp(0, last) as usize;
+            let nc = (c as i8 + dc).clamp(0, last) as usize;
             self.active_cell = Some((nr, nc));
         } else {
             self.active_cell = Some((0,0));
@@ -129,57 +343,143 @@ impl GameRenderer {
     }
 
     fn try_set(&mut self, v: u32) {
+        if v as usize > self.size() {
+            return;
+        }
         if let Some((r,c)) = self.active_cell {
-            if !self.game_mut().set_value(r, c, v) {
-                self.game_mut().mistakes+=1;
-            }
+            self.hint = None;
+            self.game_mut().set_value(r, c, v);
         }
     }
 
-    fn undo(&self) {
-        self.game_mut().pop_state();
+    fn undo(&mut self) {
+        self.hint = None;
+        self.game_mut().undo();
     }
 
-    fn clear(&self) {
+    fn redo(&mut self) {
+        self.hint = None;
+        self.game_mut().redo();
+    }
+
+    fn clear(&mut self) {
         if let Some((r, c)) = self.active_cell {
+            self.hint = None;
             self.game_mut().clear_cell(r, c);
         }
     }
 
-    fn toggle_notes_mode(&mut self) {
-        self.notes_mode = !self.notes_mode;
+    fn fill_candidates(&mut self) {
+        self.hint = None;
+        self.game_mut().fill_candidates();
     }
 
-    fn hint(&self) {
-        if let Some((r, c)) = self.active_cell {
-            let value = self.game_mut().board.solution[r][c];
-            self.game_mut().set_value(r, c, value);
+    fn toggle_auto_notes(&mut self) {
+        self.hint = None;
+        self.game_mut().toggle_auto_notes();
+    }
+
+    /// Runs a menu item. Options step to their next value.
+    fn activate(&mut self, item: MenuItem) {
+        match item {
+            MenuItem::Resume => self.close_menu(),
+            MenuItem::NewGame => {
+                self.new_game();
+                self.screen = Screen::Board;
+            }
+            MenuItem::Restart => {
+                self.replay();
+                self.screen = Screen::Board;
+            }
+            MenuItem::Quit => self.quit = true,
+            _ => self.change_option(item, 1),
+        }
+    }
+
+    fn change_option(&mut self, item: MenuItem, step: i32) {
+        match item {
+            MenuItem::Theme => self.cycle_theme(step),
+            _ => self.menu.change(item, step),
         }
     }
 
-    fn get_cell(&self, x: i32, y: i32) -> (usize, usize) {
-        let mod_y = (y - self.board_position.y) % self.cell_size;
-        (((y - mod_y) / self.cell_size) as usize, self.get_col(x))
+    fn cycle_theme(&mut self, step: i32) {
+        self.themes.cycle(step);
+        self.menu.theme = self.theme().name.clone();
+        self.theme_changed = true;
     }
 
-    fn get_col(&self, x: i32) -> usize {
-        let mod_x = (x - self.board_position.x) % self.cell_size;
-        ((x - mod_x) / self.cell_size) as usize
+    fn theme(&self) -> &Theme {
+        self.themes.current()
     }
 
-    fn get_button(&self, x: i32) -> u8 {
-        let col = self.get_col(x);
-        match col {
-            1 => 1,
-            3 => 2,
-            5 => 3,
-            7 => 4,
-            _ => 0,
+    fn close_menu(&mut self) {
+        self.screen = Screen::Board;
+        if !self.paused_before_menu {
+            self.game_mut().resume();
         }
     }
 
-    fn between(&self, v: i32, start: i32, end: i32) -> bool {
-        v > start && v < end
+    fn toggle_pause(&mut self) {
+        let mut game = self.game_mut();
+        if game.is_paused() {
+            game.resume();
+        } else {
+            game.pause();
+        }
+    }
+
+    fn resume(&mut self) {
+        if let Some(mut game) = self.saved.take() {
+            game.resume();
+            self.menu.options.shape = game.board.shape;
+            *self.game_mut() = game;
+            self.resize();
+        }
+    }
+
+    fn discard_saved(&mut self) {
+        self.saved = None;
+        self.game_mut().resume();
+    }
+
+    fn new_game(&mut self) {
+        self.hint = None;
+        self.active_cell = None;
+        self.game_mut().new_game(self.menu.options);
+        self.resize();
+    }
+
+    fn replay(&mut self) {
+        self.hint = None;
+        self.active_cell = None;
+        self.game_mut().replay();
+    }
+
+    fn cycle_check_mode(&mut self) {
+        let mut game = self.game_mut();
+        game.check_mode = game.check_mode.next();
+    }
+
+    fn toggle_notes_mode(&mut self) {
+        self.notes_mode = !self.notes_mode;
+    }
+
+    fn hint(&mut self) {
+        let hint = self.game_mut().hint(self.active_cell);
+        self.hint = hint;
+    }
+
+    fn size(&self) -> usize {
+        self.game.borrow().board.shape.size
+    }
+
+    fn board_size(&self) -> i32 {
+        self.layout.board_size(self.game.borrow().board.shape)
+    }
+
+    fn board(&self) -> Rect {
+        self.layout.board(self.game.borrow().board.shape)
     }
 
     fn draw_board(&self) -> Result<(), String> {
@@ -191,114 +491,301 @@ impl GameRenderer {
         Ok(())
     }
 
-    fn draw_status(&self) -> Result<(), String> {
-        let status = format!("Errors: {}", self.game.borrow().mistakes);
-        let elapsed = self.game.borrow().time.elapsed();
-        let total_seconds = elapsed.as_secs();
-        let minutes = total_seconds / 60;
-        let seconds = total_seconds % 60;
-        let time = format!("Time: {:02}:{:02}", minutes, seconds);        
-        let ttf_context = ttf::init().map_err(|e| e.to_string())?;
-        let font = ttf_context.load_font(FONT_PATH, 20)?;
-        let surface_errors = font
-            .render(&status)
-            .blended(Color::BLACK)
-            .map_err(|e| e.to_string())?;
-        let surface_time = font
-            .render(&time)
-            .blended(Color::BLACK)
-            .map_err(|e| e.to_string())?;
-        let texture_creator = self.canvas_mut().texture_creator();
-        if let Ok(texture) = texture_creator.create_texture_from_surface(&surface_errors) {
-            let target = Rect::new(0, 10, surface_errors.width(), surface_errors.height());
-            self.canvas_mut().copy(&texture, None, Some(target))?;
-        };
-        if let Ok(texture) = texture_creator.create_texture_from_surface(&surface_time) {
-            let x = self.cell_size * 9 - (surface_time.width() as i32);
-            let target = Rect::new(x, 10, surface_time.width(), surface_time.height());
-            self.canvas_mut().copy(&texture, None, Some(target))?;
+    /// Draws an empty board in place of the puzzle, so that it can't be
+    /// studied while the clock is stopped.
+    fn draw_paused(&self) -> Result<(), String> {
+        let board = self.board();
+        self.set_color(&self.theme().grid);
+        self.canvas_mut().draw_rect(board)?;
+        let center = board.center().x();
+        let mut y = board.center().y() - self.layout.scaled(60);
+        y += self.draw_centered("Paused", Weight::Bold, self.layout.font(48), center, y)?;
+        let text = "Press P or click the board to go on";
+        let size = self.layout.font(24);
+        self.draw_centered(text, Weight::Regular, size, center, y + self.layout.scaled(10))?;
+        Ok(())
+    }
+
+    fn menu_top(&self) -> i32 {
+        self.layout.board_position.y + self.layout.scaled(100)
+    }
+
+    /// Lists the menu items, one row each, with the selected one highlighted.
+    fn draw_menu(&self) -> Result<(), String> {
+        let board = self.board();
+        let width = board.width();
+        let center = board.center().x();
+        let title_size = self.layout.font(48);
+        self.draw_centered("Sumdoku", Weight::Bold, title_size, center, board.y())?;
+        let row_height = self.layout.scaled(MENU_ROW_HEIGHT);
+        let font_size = self.layout.font(28);
+        for (i, &item) in MenuItem::ALL.iter().enumerate() {
+            let y = self.menu_top() + i as i32 * row_height;
+            if i == self.menu.selected {
+                self.set_color(&self.theme().highlight);
+                let row = Rect::new(board.x(), y, width, row_height as u32);
+                self.canvas_mut().fill_rect(row)?;
+            }
+            let label = if item.is_option() {
+                format!("< {} >", self.menu.label(item))
+            } else {
+                self.menu.label(item)
+            };
+            let font = self.assets.font(Weight::Regular, font_size)?;
+            let (_, height) = font.size_of(&label).map_err(|e| e.to_string())?;
+            let text_y = y + (row_height - height as i32) / 2;
+            self.draw_centered(&label, Weight::Regular, font_size, center, text_y)?;
         }
         Ok(())
     }
 
+    fn draw_status(&self) -> Result<(), String> {
+        // mistakes are only counted when digits are checked as they go in
+        let status = match self.game.borrow().check_mode {
+            CheckMode::Immediate => format!("Errors: {}", self.game.borrow().mistakes),
+            mode => mode.to_string(),
+        };
+        let time = if self.game.borrow().is_paused() {
+            "Paused".to_string()
+        } else {
+            format!("Time: {}", format_time(self.game.borrow().elapsed()))
+        };
+        let difficulty = self.game.borrow().board.difficulty.to_string();
+        let area = self.layout.status;
+        let size = self.layout.font(20);
+        let y = area.y() + self.layout.scaled(10);
+        let status = self.assets.text(&status, Weight::Regular, size, self.theme().text)?;
+        self.draw_texture(&status, area.x(), y)?;
+        let time = self.assets.text(&time, Weight::Regular, size, self.theme().text)?;
+        let x = area.right() - texture_size(&time).0 as i32;
+        self.draw_texture(&time, x, y)?;
+        let text = self.theme().text;
+        let difficulty = self.assets.text(&difficulty, Weight::Regular, size, text)?;
+        let x = area.center().x() - texture_size(&difficulty).0 as i32 / 2;
+        self.draw_texture(&difficulty, x, y)
+    }
+
     fn draw_buttons(&self) -> Result<(), String> {
-        let img_size = 60i32;
-        let y = 9 * self.cell_size + self.board_position.y + 20;
-        let mut x = self.cell_size;
-        let texture_creator = self.canvas_mut().texture_creator();
-        self.add_image(x, y, img_size as u32, UNDO_ICON, &texture_creator)?;
-        x += 2 * self.cell_size;
-        self.add_image(x, y, img_size as u32, ERASE_ICON, &texture_creator)?;
-        x += 2 * self.cell_size;
-        if self.notes_mode {
-            self.set_color(&self.highlight_color);
-            self.canvas_mut()
-                .fill_rect(Rect::new(x, y, img_size as u32, img_size as u32))?;
-        }
-        self.add_image(x, y, img_size as u32, NOTE_ICON, &texture_creator)?;
-        x += 2 * self.cell_size;
-        self.add_image(x, y, img_size as u32, HINT_ICON, &texture_creator)?;
+        // redo is undo pointing the other way
+        let icons = [
+            (UNDO_ICON, false),
+            (UNDO_ICON, true),
+            (ERASE_ICON, false),
+            (NOTE_ICON, false),
+            (HINT_ICON, false),
+        ];
+        for (i, (icon, flip)) in icons.into_iter().enumerate() {
+            let button = self.layout.button(i);
+            let background = if i == 3 && self.notes_mode {
+                self.theme().highlight
+            } else {
+                self.theme().button
+            };
+            self.set_color(&background);
+            self.canvas_mut().fill_rect(button)?;
+            self.add_image(button, icon, flip)?;
+        }
+        if self.game.borrow().auto_notes {
+            let notes = self.layout.button(3);
+            let size = self.layout.font(14);
+            let label = self.assets.text("auto", Weight::Regular, size, self.theme().text)?;
+            let label_x = notes.center().x() - texture_size(&label).0 as i32 / 2;
+            self.draw_texture(&label, label_x, notes.bottom())?;
+        }
         Ok(())
     }
 
     fn add_image(
         &self,
-        x: i32,
-        y: i32,
-        size: u32,
-        path: &str,
-        texture_creator: &TextureCreator<WindowContext>,
+        target: Rect,
+        path: &'static str,
+        flip_horizontal: bool,
     ) -> Result<(), String> {
-        let image_texture = texture_creator
-            .load_texture(path)
-            .map_err(|e| e.to_string())?;
-        let target = Rect::new(x, y, size, size);
-        self.canvas_mut().copy(&image_texture, None, target)?;
+        let image_texture = self.assets.icon(path)?;
+        self.canvas_mut()
+            .copy_ex(&image_texture, None, target, 0.0, None, flip_horizontal, false)?;
         Ok(())
     }
 
     fn draw_number_picker(&self) -> Result<(), String> {
-        let cs = self.cell_size as u32;
-        let ttf_context = ttf::init().map_err(|e| e.to_string())?;
-        let font = ttf_context.load_font(FONT_PATH, cs as u16)?;
-        let texture_creator = self.canvas_mut().texture_creator();
-        self.canvas_mut().set_draw_color(self.grid_color);
-        let y = 100 + self.board_position.y + (9 * self.cell_size);
-        for i in 0..9 {
-            let x = i * self.cell_size;
-            let rect = Rect::new(x, y, cs, cs);
-            self.canvas_mut().draw_rect(rect)?;
-            let surface = font
-                .render(&(i + 1).to_string())
-                .blended(Color::BLACK)
-                .map_err(|e| e.to_string())?;
-            if let Ok(texture) = texture_creator.create_texture_from_surface(&surface) {
-                let ox = i * self.cell_size + (self.cell_size - surface.width() as i32) / 2;
-                let oy = y + (self.cell_size - surface.height() as i32) / 2;
-                let target = Rect::new(ox, oy, surface.width(), surface.height());
-                self.canvas_mut().copy(&texture, None, Some(target))?;
+        let width = self.layout.key_width;
+        let height = self.layout.key_height;
+        // two digit numbers need a smaller font to fit the box
+        let font_size = if self.size() > 9 { width / 2 } else { height };
+        for i in 0..self.size() {
+            let key = self.layout.key(i);
+            self.set_color(&self.theme().grid);
+            self.canvas_mut().draw_rect(key)?;
+            let digit = (i + 1).to_string();
+            let size = font_size as u16;
+            let texture = self.assets.text(&digit, Weight::Regular, size, self.theme().text)?;
+            let (text_width, text_height) = texture_size(&texture);
+            let ox = key.center().x() - text_width as i32 / 2;
+            let oy = key.center().y() - text_height as i32 / 2;
+            self.draw_texture(&texture, ox, oy)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the hint text under the number picker, wrapped to the width
+    /// of its area.
+    fn draw_hint(&self) -> Result<(), String> {
+        let Some(hint) = &self.hint else {
+            return Ok(());
+        };
+        let area = self.layout.hint;
+        let size = self.layout.font(16);
+        let font = self.assets.font(Weight::Regular, size)?;
+        let mut lines: Vec<String> = Vec::new();
+        for word in hint.text.split_whitespace() {
+            let fits = match lines.last() {
+                Some(line) => {
+                    let (width, _) = font
+                        .size_of(&format!("{} {}", line, word))
+                        .map_err(|e| e.to_string())?;
+                    width <= area.width()
+                }
+                None => false,
+            };
+            match lines.last_mut() {
+                Some(line) if fits => {
+                    line.push(' ');
+                    line.push_str(word);
+                }
+                _ => lines.push(word.to_string()),
             }
         }
+        let mut y = area.y();
+        for line in lines {
+            let texture = self.assets.text(&line, Weight::Regular, size, self.theme().grid)?;
+            self.draw_texture(&texture, area.x(), y)?;
+            y += font.recommended_line_spacing();
+        }
+        Ok(())
+    }
+
+    /// The two buttons of a panel over the board.
+    fn overlay_buttons(&self) -> (Rect, Rect) {
+        let board = self.board();
+        let width = board.width() / 4;
+        let height = self.layout.scaled(50) as u32;
+        let gap = self.layout.scaled(10);
+        let y = board.center().y() + self.layout.scaled(60);
+        let center = board.center().x();
+        (
+            Rect::new(center - width as i32 - gap, y, width, height),
+            Rect::new(center + gap, y, width, height),
+        )
+    }
+
+    /// Covers the solved board with the time, mistakes and hints it took.
+    fn draw_results(&self) -> Result<(), String> {
+        let game = self.game.borrow();
+        if !game.is_complete() {
+            return Ok(());
+        }
+        let mut lines = vec![format!("Time: {}", format_time(game.elapsed()))];
+        if game.check_mode == CheckMode::Immediate {
+            lines.push(format!("Errors: {}", game.mistakes));
+        }
+        lines.push(format!("Hints: {}", game.hints));
+        self.draw_overlay("Solved!", &lines, ["New game", "Replay"])
+    }
+
+    fn draw_resume_prompt(&self) -> Result<(), String> {
+        let Some(game) = &self.saved else {
+            return Ok(());
+        };
+        let size = game.board.shape.size;
+        let filled = game.cells.iter().flatten().filter(|c| c.value != 0).count();
+        let lines = [
+            format!("{}x{} {}", size, size, game.board.difficulty),
+            format!("{} of {} cells filled", filled, size * size),
+            format!("Time: {}", format_time(game.elapsed())),
+        ];
+        self.draw_overlay("Resume game?", &lines, ["Resume", "New game"])
+    }
+
+    /// Dims the board and puts a title, a few lines and two buttons on it.
+    fn draw_overlay(
+        &self,
+        title: &str,
+        lines: &[String],
+        buttons: [&str; 2],
+    ) -> Result<(), String> {
+        let board = self.board();
+        self.canvas_mut().set_blend_mode(BlendMode::Blend);
+        self.set_color(&self.theme().overlay);
+        self.canvas_mut().fill_rect(board)?;
+        self.canvas_mut().set_blend_mode(BlendMode::None);
+
+        let center = board.center().x();
+        let mut y = board.center().y() - self.layout.scaled(160);
+        y += self.draw_centered(title, Weight::Bold, self.layout.font(48), center, y)?;
+        y += self.layout.scaled(10);
+        let size = self.layout.font(24);
+        for line in lines {
+            y += self.draw_centered(line, Weight::Regular, size, center, y)?;
+        }
+
+        let (left, right) = self.overlay_buttons();
+        for (rect, label) in [(left, buttons[0]), (right, buttons[1])] {
+            self.set_color(&self.theme().active_cell);
+            self.canvas_mut().fill_rect(rect)?;
+            self.set_color(&self.theme().grid);
+            self.canvas_mut().draw_rect(rect)?;
+            let font = self.assets.font(Weight::Regular, size)?;
+            let (_, height) = font.size_of(label).map_err(|e| e.to_string())?;
+            let text_y = rect.y() + (rect.height() as i32 - height as i32) / 2;
+            self.draw_centered(label, Weight::Regular, size, rect.center().x(), text_y)?;
+        }
         Ok(())
     }
 
+    /// Writes `text` centred on `x` and returns the height it took.
+    fn draw_centered(
+        &self,
+        text: &str,
+        weight: Weight,
+        size: u16,
+        x: i32,
+        y: i32,
+    ) -> Result<i32, String> {
+        let texture = self.assets.text(text, weight, size, self.theme().grid)?;
+        let (width, height) = texture_size(&texture);
+        self.draw_texture(&texture, x - width as i32 / 2, y)?;
+        Ok(height as i32)
+    }
+
+    /// Copies a texture to the canvas at its own size.
+    fn draw_texture(&self, texture: &Texture, x: i32, y: i32) -> Result<(), String> {
+        let (width, height) = texture_size(texture);
+        let target = Rect::new(x, y, width, height);
+        self.canvas_mut().copy(texture, None, Some(target))
+    }
+
     fn draw_grid(&self) -> Result<(), String> {
         let zero = 0;
-        let end = 9 * self.cell_size;
-        self.set_color(&self.grid_color);
-        for i in 0..10 {
-            let xs = i * self.cell_size;
+        let end = self.board_size();
+        let shape = self.game.borrow().board.shape;
+        self.set_color(&self.theme().grid);
+        for i in 0..=shape.size as i32 {
+            let xs = i * self.layout.cell_size;
             // vertical
             self.line(&xs, &zero, &xs, &end)?;
             // horizontal
             self.line(&zero, &xs, &end, &xs)?;
         }
-        for i in 0..4 {
-            let a = 3 * i * self.cell_size - 1;
-            let b = 3 * i * self.cell_size + 1;
+        for i in (0..=shape.size).step_by(shape.box_cols) {
+            let a = i as i32 * self.layout.cell_size - 1;
+            let b = i as i32 * self.layout.cell_size + 1;
             // vertical
             self.line(&a, &zero, &a, &end)?;
             self.line(&b, &zero, &b, &end)?;
+        }
+        for i in (0..=shape.size).step_by(shape.box_rows) {
+            let a = i as i32 * self.layout.cell_size - 1;
+            let b = i as i32 * self.layout.cell_size + 1;
             // horizontal
             self.line(&zero, &a, &end, &a)?;
             self.line(&zero, &b, &end, &b)?;
@@ -307,40 +794,38 @@ impl GameRenderer {
     }
 
     fn draw_notes(&self) -> Result<(), String> {
-        let notes_area_size = self.cell_size - 2 * self.cage_offset;
-        let note_cell_size = (notes_area_size - 4 * self.notes_gap) / 3;
-        let ttf_context = ttf::init().map_err(|e| e.to_string())?;
-        let font = ttf_context.load_font(FONT_PATH, note_cell_size as u16)?;
-        let bold_font = ttf_context.load_font(BOLD_FONT_PATH, note_cell_size as u16)?;
-        for r in 0..9 {
-            for c in 0..9 {
+        let shape = self.game.borrow().board.shape;
+        // notes are laid out like the digits of a box
+        let across = shape.box_cols as i32;
+        let down = shape.box_rows as i32;
+        let offset = self.layout.cage_offset;
+        let gap = self.layout.notes_gap;
+        let notes_area_size = self.layout.cell_size - 2 * offset;
+        let note_cell_size = (notes_area_size - (across + 1) * gap) / across.max(down);
+        for r in 0..shape.size {
+            for c in 0..shape.size {
                 let cell = &self.game.borrow().cells[r][c];
-                let cx = c as i32 * self.cell_size + self.board_position.x;
-                let cy = r as i32 * self.cell_size + self.board_position.y;
-                for n in 1..=9 {
-                    let nr = (n - 1) / 3;
-                    let nc = (n - 1) % 3;
+                let cx = c as i32 * self.layout.cell_size + self.layout.board_position.x;
+                let cy = r as i32 * self.layout.cell_size + self.layout.board_position.y;
+                for n in 1..=shape.size as i32 {
+                    let nr = (n - 1) / across;
+                    let nc = (n - 1) % across;
                     let mask = 1u16 << n;
                     let nv = cell.notes & mask;
                     if nv == 0 {
                         continue;
                     }
-                    let active_font = if self.active_number == nv as u32 {
-                        &bold_font
+                    let weight = if self.active_number == nv as u32 {
+                        Weight::Bold
                     } else {
-                        &font
-                    };
-                    let surface = active_font
-                        .render(&n.to_string())
-                        .blended(self.grid_color)
-                        .map_err(|e| e.to_string())?;
-                    let texture_creator = self.canvas_mut().texture_creator();
-                    if let Ok(texture) = texture_creator.create_texture_from_surface(&surface) {
-                        let x = cx + 3 * self.cage_offset + nc * note_cell_size + self.notes_gap;
-                        let y = cy + self.cage_offset + nr * note_cell_size + self.notes_gap;
-                        let target = Rect::new(x, y, surface.width(), surface.height());
-                        self.canvas_mut().copy(&texture, None, Some(target))?;
+                        Weight::Regular
                     };
+                    let digit = n.to_string();
+                    let size = note_cell_size as u16;
+                    let texture = self.assets.text(&digit, weight, size, self.theme().grid)?;
+                    let x = cx + 3 * offset + nc * note_cell_size + gap;
+                    let y = cy + offset + nr * note_cell_size + gap;
+                    self.draw_texture(&texture, x, y)?;
                 }
             }
         }
@@ -348,15 +833,19 @@ impl GameRenderer {
     }
 
     fn draw_cages(&self) -> Result<(), String> {
-        let c = self.cage_color;
-        self.set_color(&c);
+        let c = self.theme().cage;
+        let conflicts = self.game.borrow().visible_conflicts();
         let mut game = self.game_mut();
         for cage in game.board.cages.iter_mut() {
             if cage.lines == None {
                 let lines: Vec<((i32, i32), (i32, i32))> = self.get_cage_lines(cage);
                 cage.lines = Some(lines);
             }
-            self.draw_sum(cage)?;
+            let wrong_sum = conflicts.iter().any(|conflict| {
+                conflict.kind == ConflictKind::CageSum && conflict.cells == cage.cells
+            });
+            self.draw_sum(cage, if wrong_sum { self.theme().error } else { c })?;
+            self.set_color(&c);
             if let Some(lines) = &cage.lines {
                 for line in lines {
                     self.line(&line.0 .0, &line.0 .1, &line.1 .0, &line.1 .1)?;
@@ -367,48 +856,42 @@ impl GameRenderer {
     }
 
     fn draw_numbers(&self) -> Result<(), String> {
-        let ttf_context = ttf::init().map_err(|e| e.to_string())?;
-        let rect_size = (self.cell_size as f32 * 0.6) as u32;
-        let font = ttf_context.load_font(FONT_PATH, rect_size as u16)?;
-        let bold_font = ttf_context.load_font(BOLD_FONT_PATH, rect_size as u16)?;
-        for r in 0..9 {
-            let cy = r as i32 * self.cell_size + self.board_position.y;
-            for c in 0..9 {
+        let rect_size = (self.layout.cell_size as f32 * 0.6) as u32;
+        let size = self.size();
+        let flagged = self.game.borrow().flagged_cells();
+        for r in 0..size {
+            let cy = r as i32 * self.layout.cell_size + self.layout.board_position.y;
+            for c in 0..size {
                 let game = self.game.borrow();
                 let cell = &game.cells[r][c];
                 if cell.value == 0 {
                     continue;
                 }
-                let color = if cell.value == game.board.solution[r][c] {
-                    self.grid_color
+                let color = if flagged.contains(&Cell::new(r, c)) {
+                    self.theme().error
                 } else {
-                    Color::RED
+                    self.theme().grid
                 };
-                let active_font = if cell.value == self.active_number {
-                    &bold_font
+                let weight = if cell.value == self.active_number {
+                    Weight::Bold
                 } else {
-                    &font
-                };
-                let surface = active_font
-                    .render(&cell.value.to_string())
-                    .blended(color)
-                    .map_err(|e| e.to_string())?;
-                let texture_creator = self.canvas_mut().texture_creator();
-                if let Ok(texture) = texture_creator.create_texture_from_surface(&surface) {
-                    let cx = c as i32 * self.cell_size + self.board_position.x;
-                    let ox = (self.cell_size - surface.width() as i32) / 2;
-                    let oy = (self.cell_size - surface.height() as i32) / 2;
-                    let target = Rect::new(cx + ox, cy + oy, surface.width(), surface.height());
-                    self.canvas_mut().copy(&texture, None, Some(target))?;
+                    Weight::Regular
                 };
+                let digit = cell.value.to_string();
+                let texture = self.assets.text(&digit, weight, rect_size as u16, color)?;
+                let (width, height) = texture_size(&texture);
+                let cx = c as i32 * self.layout.cell_size + self.layout.board_position.x;
+                let ox = (self.layout.cell_size - width as i32) / 2;
+                let oy = (self.layout.cell_size - height as i32) / 2;
+                self.draw_texture(&texture, cx + ox, cy + oy)?;
             }
         }
         Ok(())
     }
 
     fn to_point(&self, x: &i32, y: &i32) -> Point {
-        let px = *x + self.board_position.x;
-        let py = *y + self.board_position.y;
+        let px = *x + self.layout.board_position.x;
+        let py = *y + self.layout.board_position.y;
         Point::new(px, py)
     }
 
@@ -434,17 +917,17 @@ impl GameRenderer {
         let mut lines = vec![];
 
         for cell in &cage.cells {
-            let x = cell.col as i32 * self.cell_size;
-            let y = cell.row as i32 * self.cell_size;
+            let x = cell.col as i32 * self.layout.cell_size;
+            let y = cell.row as i32 * self.layout.cell_size;
 
             let mut draw_top = true;
             let mut draw_bottom = true;
             let mut draw_left = true;
             let mut draw_right = true;
-            let mut offset_x1 = self.cage_offset;
-            let mut offset_x2 = self.cage_offset;
-            let mut offset_y1 = self.cage_offset;
-            let mut offset_y2 = self.cage_offset;
+            let mut offset_x1 = self.layout.cage_offset;
+            let mut offset_x2 = self.layout.cage_offset;
+            let mut offset_y1 = self.layout.cage_offset;
+            let mut offset_y2 = self.layout.cage_offset;
             let mut top_left = false;
             let mut top_right = false;
             let mut bottom_left = false;
@@ -469,7 +952,7 @@ impl GameRenderer {
                         offset_x2 = 0;
                     }
                     if neighbor.row < cell.row && neighbor.col != cell.col {
-                        offset_y1 = -self.cage_offset;
+                        offset_y1 = -self.layout.cage_offset;
                     }
                 } else if cage.is_cross_join(cell, neighbor) {
                     bottom_left |= neighbor.row > cell.row && neighbor.col < cell.col;
@@ -481,66 +964,66 @@ impl GameRenderer {
 
             if draw_top {
                 let ox1 = if top_left {
-                    -self.cage_offset
+                    -self.layout.cage_offset
                 } else {
                     offset_x1
                 };
                 let ox2 = if top_right {
-                    -self.cage_offset
+                    -self.layout.cage_offset
                 } else {
                     offset_x2
                 };
                 lines.push((
                     (x + ox1, y + offset_y1),
-                    (x + self.cell_size - ox2, y + offset_y1),
+                    (x + self.layout.cell_size - ox2, y + offset_y1),
                 ));
             }
             if draw_bottom {
                 let ox1 = if bottom_left {
-                    -self.cage_offset
+                    -self.layout.cage_offset
                 } else {
                     offset_x1
                 };
                 let ox2 = if bottom_right {
-                    -self.cage_offset
+                    -self.layout.cage_offset
                 } else {
                     offset_x2
                 };
                 lines.push((
-                    (x + ox1, y + self.cell_size - offset_y2),
-                    (x + self.cell_size - ox2, y + self.cell_size - offset_y2),
+                    (x + ox1, y + self.layout.cell_size - offset_y2),
+                    (x + self.layout.cell_size - ox2, y + self.layout.cell_size - offset_y2),
                 ));
             }
             if draw_left {
                 let oy1 = if top_left {
-                    -self.cage_offset
+                    -self.layout.cage_offset
                 } else {
                     offset_y1
                 };
                 let oy2 = if bottom_left {
-                    -self.cage_offset
+                    -self.layout.cage_offset
                 } else {
                     offset_y2
                 };
                 lines.push((
                     (x + offset_x1, y + oy1),
-                    (x + offset_x1, y + self.cell_size - oy2),
+                    (x + offset_x1, y + self.layout.cell_size - oy2),
                 ));
             }
             if draw_right {
                 let oy1 = if top_right {
-                    -self.cage_offset
+                    -self.layout.cage_offset
                 } else {
                     offset_y1
                 };
                 let oy2 = if bottom_right {
-                    -self.cage_offset
+                    -self.layout.cage_offset
                 } else {
                     offset_y2
                 };
                 lines.push((
-                    (x + self.cell_size - offset_x2, y + oy1),
-                    (x + self.cell_size - offset_x2, y + self.cell_size - oy2),
+                    (x + self.layout.cell_size - offset_x2, y + oy1),
+                    (x + self.layout.cell_size - offset_x2, y + self.layout.cell_size - oy2),
                 ));
             }
         }
@@ -548,62 +1031,75 @@ impl GameRenderer {
         lines
     }
 
-    fn draw_sum(&self, cage: &Cage) -> Result<(), String> {
-        let x = cage.cells[0].col as i32 * self.cell_size
-            + self.cage_offset
+    fn draw_sum(&self, cage: &Cage, color: Color) -> Result<(), String> {
+        let x = cage.cells[0].col as i32 * self.layout.cell_size
+            + self.layout.cage_offset
             + 3
-            + self.board_position.x;
-        let y = cage.cells[0].row as i32 * self.cell_size
-            + self.cage_offset
+            + self.layout.board_position.x;
+        let y = cage.cells[0].row as i32 * self.layout.cell_size
+            + self.layout.cage_offset
             + 3
-            + self.board_position.y;
-        let font_size = 10u16;
-        let ttf_context = ttf::init().map_err(|e| e.to_string())?;
-        let mut font = ttf_context.load_font(FONT_PATH, font_size)?;
-        font.set_style(ttf::FontStyle::NORMAL);
-        let surface = font
-            .render(&cage.sum.to_string())
-            .blended(self.cage_color)
-            .map_err(|e| e.to_string())?;
-        let texture_creator = self.canvas_mut().texture_creator();
-        if let Ok(texture) = texture_creator.create_texture_from_surface(&surface) {
-            let target = Rect::new(
-                x,
-                y,
-                u32::from(surface.rect().width()),
-                u32::from(surface.rect().height()),
-            );
-            self.canvas_mut().copy(&texture, None, Some(target))?;
-        };
-        Ok(())
+            + self.layout.board_position.y;
+        let size = self.layout.font(10);
+        let texture = self.assets.text(&cage.sum.to_string(), Weight::Regular, size, color)?;
+        self.draw_texture(&texture, x, y)
     }
 
     fn highlight_cells(&self) -> Result<(), String> {
         let current_color = self.canvas_mut().draw_color();
         if let Some((ar, ac)) = self.active_cell {
-            for i in 0..9 {
+            for i in 0..self.size() {
                 self.highlight_cell(ar, ac, ar, i)?;
                 self.highlight_cell(ar, ac, i, ac)?;
             }
         }
+        if let Some(hint) = &self.hint {
+            for cell in &hint.cells {
+                self.fill_cell(cell, &self.theme().hint)?;
+            }
+            for cell in &hint.targets {
+                self.fill_cell(cell, &self.theme().hint_target)?;
+            }
+        }
         self.set_color(&current_color);
         Ok(())
     }
 
+    fn fill_cell(&self, cell: &Cell, color: &Color) -> Result<(), String> {
+        let rect = Rect::new(
+            cell.col as i32 * self.layout.cell_size + self.layout.board_position.x,
+            cell.row as i32 * self.layout.cell_size + self.layout.board_position.y,
+            self.layout.cell_size as u32,
+            self.layout.cell_size as u32,
+        );
+        self.set_color(color);
+        self.canvas_mut().fill_rect(rect)
+    }
+
     fn highlight_cell(&self, ar: usize, ac: usize, r: usize, c: usize) -> Result<(), String> {
         let color = if ar == r && ac == c {
-            &self.active_cell_background
+            &self.theme().active_cell
         } else {
-            &self.highlight_color
+            &self.theme().highlight
         };
         let active_cell = Rect::new(
-            c as i32 * self.cell_size + self.board_position.x,
-            r as i32 * self.cell_size + self.board_position.y,
-            self.cell_size as u32,
-            self.cell_size as u32,
+            c as i32 * self.layout.cell_size + self.layout.board_position.x,
+            r as i32 * self.layout.cell_size + self.layout.board_position.y,
+            self.layout.cell_size as u32,
+            self.layout.cell_size as u32,
         );
         self.set_color(&color);
         self.canvas_mut().fill_rect(active_cell)?;
         Ok(())
     }
 }
+
+fn texture_size(texture: &Texture) -> (u32, u32) {
+    let query = texture.query();
+    (query.width, query.height)
+}
+
+fn format_time(elapsed: Duration) -> String {
+    let total_seconds = elapsed.as_secs();
+    format!("{:02}:{:02}", total_seconds / 60, total_seconds % 60)
+}
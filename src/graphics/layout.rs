@@ -0,0 +1,227 @@
+//! Where the parts of the window go, worked out from its size in pixels.
+//!
+//! Everything is laid out as in a reference design with a 720 pixel board
+//! and scaled to fit. Tall windows stack the toolbar, picker and hints under
+//! the board; wide ones put them in a panel to its right.
+
+use sdl2::rect::{Point, Rect};
+
+use crate::logic::Shape;
+
+const MARGIN: i32 = 10;
+const GAP: i32 = 20;
+const STATUS_HEIGHT: i32 = 40;
+const BOARD_SIZE: i32 = 720;
+const BUTTON_SIZE: i32 = 60;
+/// Room under the toolbar for the label of the notes button.
+const LABEL_HEIGHT: i32 = 20;
+const KEY_SIZE: i32 = 80;
+const HINT_HEIGHT: i32 = 100;
+const PANEL_WIDTH: i32 = 360;
+const BUTTONS: i32 = 5;
+
+/// The reference design's width and height with everything stacked.
+const PORTRAIT: (i32, i32) = (
+    BOARD_SIZE + 2 * MARGIN,
+    STATUS_HEIGHT + BOARD_SIZE + 3 * GAP + BUTTON_SIZE + LABEL_HEIGHT + KEY_SIZE + HINT_HEIGHT,
+);
+/// The reference design's width and height with a panel beside the board.
+const LANDSCAPE: (i32, i32) = (
+    BOARD_SIZE + PANEL_WIDTH + 2 * MARGIN + GAP,
+    STATUS_HEIGHT + BOARD_SIZE + MARGIN,
+);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    Portrait,
+    Landscape,
+}
+
+#[derive(Debug, Clone)]
+pub struct Layout {
+    pub orientation: Orientation,
+    /// Size of the window against the reference design.
+    pub scale: f32,
+    pub cell_size: i32,
+    pub board_position: Point,
+    /// Mistakes, difficulty and the clock, above the board.
+    pub status: Rect,
+    pub toolbar: Rect,
+    pub picker: Rect,
+    /// Keys per row of the picker; the rest wrap onto the rows below.
+    pub picker_columns: usize,
+    pub key_width: i32,
+    pub key_height: i32,
+    /// Where hints are written, wrapped to its width.
+    pub hint: Rect,
+    /// How far cage outlines are set inside their cells.
+    pub cage_offset: i32,
+    pub notes_gap: i32,
+}
+
+impl Layout {
+    /// Lays out a `width` by `height` pixel window for a grid of `shape`.
+    pub fn new(width: u32, height: u32, shape: Shape) -> Self {
+        let fit = |(w, h): (i32, i32)| (width as f32 / w as f32).min(height as f32 / h as f32);
+        let orientation = if fit(LANDSCAPE) > fit(PORTRAIT) {
+            Orientation::Landscape
+        } else {
+            Orientation::Portrait
+        };
+        let design = match orientation {
+            Orientation::Portrait => PORTRAIT,
+            Orientation::Landscape => LANDSCAPE,
+        };
+        let size = shape.size as i32;
+        // whole pixels per cell, so the grid lines stay sharp
+        let cell_size = ((BOARD_SIZE as f32 * fit(design)) as i32 / size).max(8);
+        let board_size = cell_size * size;
+        let scale = board_size as f32 / BOARD_SIZE as f32;
+        let scaled = |v: i32| (v as f32 * scale).round() as i32;
+
+        let left = (width as i32 - scaled(design.0)) / 2 + scaled(MARGIN);
+        let top = (height as i32 - scaled(design.1)).max(0) / 2;
+        let board_position = Point::new(left, top + scaled(STATUS_HEIGHT));
+        let board_bottom = board_position.y + board_size;
+        let mut layout = Layout {
+            orientation,
+            scale,
+            cell_size,
+            board_position,
+            status: Rect::new(0, 0, 1, 1),
+            toolbar: Rect::new(0, 0, 1, 1),
+            picker: Rect::new(0, 0, 1, 1),
+            picker_columns: shape.size,
+            key_width: cell_size,
+            key_height: cell_size.min(scaled(KEY_SIZE)),
+            hint: Rect::new(0, 0, 1, 1),
+            cage_offset: (cell_size / 16).max(2),
+            notes_gap: (cell_size / 27).max(1),
+        };
+        match orientation {
+            Orientation::Portrait => {
+                let board_width = board_size as u32;
+                layout.status = Rect::new(left, top, board_width, scaled(STATUS_HEIGHT) as u32);
+                let toolbar_y = board_bottom + scaled(GAP);
+                let button_height = scaled(BUTTON_SIZE) as u32;
+                layout.toolbar = Rect::new(left, toolbar_y, board_width, button_height);
+                let picker_y = layout.toolbar.bottom() + scaled(LABEL_HEIGHT + GAP);
+                let picker_height = layout.key_height as u32;
+                layout.picker = Rect::new(left, picker_y, board_width, picker_height);
+                let hint_y = layout.picker.bottom() + scaled(GAP) / 2;
+                let hint_height = scaled(HINT_HEIGHT) as u32;
+                layout.hint = Rect::new(left, hint_y, board_width, hint_height);
+            }
+            Orientation::Landscape => {
+                let width = scaled(design.0 - 2 * MARGIN) as u32;
+                layout.status = Rect::new(left, top, width, scaled(STATUS_HEIGHT) as u32);
+                let panel_x = left + board_size + scaled(GAP);
+                let panel_width = scaled(PANEL_WIDTH);
+                let button_height = scaled(BUTTON_SIZE) as u32;
+                let toolbar =
+                    Rect::new(panel_x, board_position.y, panel_width as u32, button_height);
+                layout.toolbar = toolbar;
+                // the picker keys are laid out like the digits of a box
+                let columns = shape.box_cols as i32;
+                let rows = shape.box_rows as i32;
+                let key = (panel_width / columns).min(scaled(KEY_SIZE));
+                layout.picker_columns = shape.box_cols;
+                layout.key_width = key;
+                layout.key_height = key;
+                let picker_x = panel_x + (panel_width - key * columns) / 2;
+                let picker_y = toolbar.bottom() + scaled(LABEL_HEIGHT + GAP);
+                let picker = Rect::new(
+                    picker_x,
+                    picker_y,
+                    (key * columns) as u32,
+                    (key * rows) as u32,
+                );
+                layout.picker = picker;
+                let hint_y = picker.bottom() + scaled(GAP);
+                let hint_height = (board_bottom - hint_y).max(1) as u32;
+                layout.hint = Rect::new(panel_x, hint_y, panel_width as u32, hint_height);
+            }
+        }
+        layout
+    }
+
+    /// A window size in points that shows the whole design at `dpi_scale`
+    /// times its size, shrunk if need be to fit in `available`.
+    pub fn window_size(available: (u32, u32), dpi_scale: f32) -> (u32, u32) {
+        let (width, height) = (PORTRAIT.0 as f32, PORTRAIT.1 as f32);
+        let scale = dpi_scale
+            .min(available.0 as f32 / width)
+            .min(available.1 as f32 / height);
+        ((width * scale) as u32, (height * scale) as u32)
+    }
+
+    /// The smallest window worth laying out, in points.
+    pub fn minimum_window_size() -> (u32, u32) {
+        (PORTRAIT.0 as u32 / 2, LANDSCAPE.1 as u32 / 2)
+    }
+
+    /// A length of the reference design at the window's size.
+    pub fn scaled(&self, length: i32) -> i32 {
+        (length as f32 * self.scale).round() as i32
+    }
+
+    /// A font size of the reference design at the window's size.
+    pub fn font(&self, size: u16) -> u16 {
+        (size as f32 * self.scale).round().max(1.0) as u16
+    }
+
+    pub fn board_size(&self, shape: Shape) -> i32 {
+        self.cell_size * shape.size as i32
+    }
+
+    pub fn board(&self, shape: Shape) -> Rect {
+        let size = self.board_size(shape) as u32;
+        Rect::new(self.board_position.x, self.board_position.y, size, size)
+    }
+
+    /// The row and column of the cell at a point, if it is on the board.
+    pub fn cell_at(&self, shape: Shape, x: i32, y: i32) -> Option<(usize, usize)> {
+        if !self.board(shape).contains_point((x, y)) {
+            return None;
+        }
+        let row = (y - self.board_position.y) / self.cell_size;
+        let col = (x - self.board_position.x) / self.cell_size;
+        Some((row as usize, col as usize))
+    }
+
+    /// The toolbar button at `index`, counting from the left.
+    pub fn button(&self, index: usize) -> Rect {
+        let size = self
+            .scaled(BUTTON_SIZE)
+            .min(self.toolbar.width() as i32 / (BUTTONS + 1));
+        let slot = self.toolbar.width() as i32 / BUTTONS;
+        let center = self.toolbar.x() + slot * index as i32 + slot / 2;
+        Rect::new(
+            center - size / 2,
+            self.toolbar.y(),
+            size as u32,
+            size as u32,
+        )
+    }
+
+    pub fn button_at(&self, x: i32, y: i32) -> Option<usize> {
+        (0..BUTTONS as usize).find(|&index| self.button(index).contains_point((x, y)))
+    }
+
+    /// The picker key for the digit `index + 1`.
+    pub fn key(&self, index: usize) -> Rect {
+        let row = (index / self.picker_columns) as i32;
+        let col = (index % self.picker_columns) as i32;
+        Rect::new(
+            self.picker.x() + col * self.key_width,
+            self.picker.y() + row * self.key_height,
+            self.key_width as u32,
+            self.key_height as u32,
+        )
+    }
+
+    /// The index of the picker key at a point, if there is one.
+    pub fn key_at(&self, shape: Shape, x: i32, y: i32) -> Option<usize> {
+        (0..shape.size).find(|&index| self.key(index).contains_point((x, y)))
+    }
+}
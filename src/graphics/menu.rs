@@ -0,0 +1,123 @@
+use crate::{
+    game::Options,
+    logic::{Difficulty, Shape},
+};
+
+const DIFFICULTIES: [Difficulty; 4] = [
+    Difficulty::Easy,
+    Difficulty::Medium,
+    Difficulty::Hard,
+    Difficulty::Fiendish,
+];
+
+/// What the window shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Screen {
+    Board,
+    Menu,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuItem {
+    Resume,
+    NewGame,
+    Restart,
+    Size,
+    Difficulty,
+    CageSize,
+    Theme,
+    Quit,
+}
+
+impl MenuItem {
+    pub const ALL: [MenuItem; 8] = [
+        MenuItem::Resume,
+        MenuItem::NewGame,
+        MenuItem::Restart,
+        MenuItem::Size,
+        MenuItem::Difficulty,
+        MenuItem::CageSize,
+        MenuItem::Theme,
+        MenuItem::Quit,
+    ];
+
+    /// Whether the item picks a setting rather than doing something.
+    pub fn is_option(self) -> bool {
+        matches!(
+            self,
+            MenuItem::Size | MenuItem::Difficulty | MenuItem::CageSize | MenuItem::Theme
+        )
+    }
+}
+
+/// The menu's selected row, the options the next new game is made with and
+/// the name of the theme in use.
+pub struct Menu {
+    pub selected: usize,
+    pub options: Options,
+    pub theme: String,
+}
+
+impl Menu {
+    pub fn new(options: Options, theme: String) -> Self {
+        Menu {
+            selected: 0,
+            options,
+            theme,
+        }
+    }
+
+    pub fn selected_item(&self) -> MenuItem {
+        MenuItem::ALL[self.selected]
+    }
+
+    pub fn label(&self, item: MenuItem) -> String {
+        let size = self.options.shape.size;
+        match item {
+            MenuItem::Resume => "Resume".to_string(),
+            MenuItem::NewGame => "New game".to_string(),
+            MenuItem::Restart => "Restart puzzle".to_string(),
+            MenuItem::Size => format!("Size: {}x{}", size, size),
+            MenuItem::Difficulty => format!("Difficulty: {}", self.options.difficulty),
+            MenuItem::CageSize => format!("Max cage size: {}", self.options.max_cage_size),
+            MenuItem::Theme => format!("Theme: {}", self.theme),
+            MenuItem::Quit => "Quit".to_string(),
+        }
+    }
+
+    /// Moves the selection up or down, wrapping around.
+    pub fn select(&mut self, step: i32) {
+        let count = MenuItem::ALL.len() as i32;
+        self.selected = (self.selected as i32 + step).rem_euclid(count) as usize;
+    }
+
+    /// Steps the value of a game option forwards or backwards, wrapping
+    /// around. Themes are switched by the renderer, which holds them.
+    pub fn change(&mut self, item: MenuItem, step: i32) {
+        let options = &mut self.options;
+        match item {
+            MenuItem::Size => {
+                let index = Shape::SUPPORTED
+                    .iter()
+                    .position(|&shape| shape == options.shape)
+                    .unwrap_or(0);
+                options.shape = Shape::SUPPORTED[cycle(index, step, Shape::SUPPORTED.len())];
+            }
+            MenuItem::Difficulty => {
+                let index = options.difficulty as usize;
+                options.difficulty = DIFFICULTIES[cycle(index, step, DIFFICULTIES.len())];
+            }
+            MenuItem::CageSize => {
+                let first = *Options::CAGE_SIZES.start();
+                let count = Options::CAGE_SIZES.count();
+                let index = options.max_cage_size.saturating_sub(first);
+                options.max_cage_size = first + cycle(index, step, count);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn cycle(index: usize, step: i32, count: usize) -> usize {
+    (index as i32 + step).rem_euclid(count as i32) as usize
+}
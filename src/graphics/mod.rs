@@ -1,3 +1,19 @@
+#[cfg(feature = "sdl")]
+pub mod assets;
+#[cfg(feature = "sdl")]
 pub mod draw;
+#[cfg(feature = "sdl")]
+pub mod layout;
+#[cfg(feature = "sdl")]
+pub mod menu;
+pub mod theme;
 
-pub use draw::*;
\ No newline at end of file
+#[cfg(feature = "sdl")]
+pub use assets::*;
+#[cfg(feature = "sdl")]
+pub use draw::*;
+#[cfg(feature = "sdl")]
+pub use layout::*;
+#[cfg(feature = "sdl")]
+pub use menu::*;
+pub use theme::*;
@@ -0,0 +1,394 @@
+//! The colours the board is drawn in.
+//!
+//! Besides the built-in themes, more can be defined in `config.json` in the
+//! per-user config directory: `$XDG_CONFIG_HOME/sumdoku` (or
+//! `~/.config/sumdoku`) on Linux, `~/Library/Application Support/sumdoku` on
+//! macOS and `%APPDATA%\sumdoku` on Windows. The file also remembers the
+//! theme last picked:
+//!
+//! ```json
+//! {
+//!     "theme": "Sepia",
+//!     "themes": [
+//!         { "name": "Sepia", "base": "Light", "background": "#f4ecd8", "cage": "#8b5a2b" }
+//!     ]
+//! }
+//! ```
+//!
+//! A theme takes the colours it leaves out from its `base`, or from the
+//! light theme. Colours are written `#rrggbb`, or `#rrggbbaa` with alpha.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "sdl")]
+use sdl2::pixels::Color;
+use serde_json::{json, Value};
+
+use crate::game::save;
+
+const CONFIG_FILE: &str = "config.json";
+
+/// Stands in for SDL's colour when the window isn't built, with the parts
+/// of it the themes use.
+#[cfg(not(feature = "sdl"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+#[cfg(not(feature = "sdl"))]
+#[allow(non_snake_case)]
+impl Color {
+    pub const WHITE: Color = Color::RGB(255, 255, 255);
+    pub const BLACK: Color = Color::RGB(0, 0, 0);
+    pub const RED: Color = Color::RGB(255, 0, 0);
+    pub const BLUE: Color = Color::RGB(0, 0, 255);
+
+    pub const fn RGB(r: u8, g: u8, b: u8) -> Color {
+        Color::RGBA(r, g, b, 255)
+    }
+
+    pub const fn RGBA(r: u8, g: u8, b: u8, a: u8) -> Color {
+        Color { r, g, b, a }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    pub background: Color,
+    /// Labels, the number picker and the status line.
+    pub text: Color,
+    /// Grid lines and the digits in cells.
+    pub grid: Color,
+    pub cage: Color,
+    /// Wrong digits and cage sums.
+    pub error: Color,
+    pub active_cell: Color,
+    /// The row and column of the active cell, and selected buttons.
+    pub highlight: Color,
+    /// Cells a hint is based on.
+    pub hint: Color,
+    /// Cells a hint is about.
+    pub hint_target: Color,
+    /// Laid over the board under the results and prompts.
+    pub overlay: Color,
+    /// Behind the toolbar icons, which are drawn in black.
+    pub button: Color,
+}
+
+impl Theme {
+    pub fn light() -> Self {
+        Theme {
+            name: "Light".to_string(),
+            background: Color::WHITE,
+            text: Color::BLACK,
+            grid: Color::BLACK,
+            cage: Color::BLUE,
+            error: Color::RED,
+            active_cell: Color::RGB(172, 200, 229),
+            highlight: Color::RGB(200, 208, 222),
+            hint: Color::RGB(250, 236, 170),
+            hint_target: Color::RGB(190, 228, 180),
+            overlay: Color::RGBA(255, 255, 255, 220),
+            button: Color::WHITE,
+        }
+    }
+
+    pub fn dark() -> Self {
+        Theme {
+            name: "Dark".to_string(),
+            background: Color::RGB(30, 32, 36),
+            text: Color::RGB(220, 222, 226),
+            grid: Color::RGB(200, 202, 208),
+            cage: Color::RGB(110, 160, 240),
+            error: Color::RGB(240, 100, 100),
+            active_cell: Color::RGB(60, 80, 110),
+            highlight: Color::RGB(48, 54, 66),
+            hint: Color::RGB(96, 86, 40),
+            hint_target: Color::RGB(50, 90, 56),
+            overlay: Color::RGBA(30, 32, 36, 225),
+            button: Color::RGB(200, 202, 208),
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Theme {
+            name: "High contrast".to_string(),
+            background: Color::WHITE,
+            text: Color::BLACK,
+            grid: Color::BLACK,
+            cage: Color::BLACK,
+            error: Color::RGB(200, 0, 0),
+            active_cell: Color::RGB(255, 220, 0),
+            highlight: Color::RGB(210, 210, 210),
+            hint: Color::RGB(255, 240, 120),
+            hint_target: Color::RGB(120, 220, 255),
+            overlay: Color::RGBA(255, 255, 255, 240),
+            button: Color::WHITE,
+        }
+    }
+
+    /// Tells errors and hints apart without relying on red and green, using
+    /// the Okabe-Ito palette.
+    pub fn deuteranopia() -> Self {
+        Theme {
+            name: "Deuteranopia".to_string(),
+            background: Color::WHITE,
+            text: Color::BLACK,
+            grid: Color::BLACK,
+            cage: Color::RGB(0, 114, 178),
+            error: Color::RGB(213, 94, 0),
+            active_cell: Color::RGB(170, 210, 240),
+            highlight: Color::RGB(215, 222, 230),
+            hint: Color::RGB(240, 228, 66),
+            hint_target: Color::RGB(86, 180, 233),
+            overlay: Color::RGBA(255, 255, 255, 220),
+            button: Color::WHITE,
+        }
+    }
+
+    pub fn builtin() -> Vec<Theme> {
+        vec![
+            Theme::light(),
+            Theme::dark(),
+            Theme::high_contrast(),
+            Theme::deuteranopia(),
+        ]
+    }
+
+    /// Reads a theme from the config file, taking what it leaves out from
+    /// the theme named by its `base`, looked up in `known`.
+    fn from_json(json: &Value, known: &[Theme]) -> Result<Self, String> {
+        let name = json
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or("theme without a name")?;
+        let mut theme = match json.get("base").and_then(Value::as_str) {
+            Some(base) => known
+                .iter()
+                .find(|theme| theme.name.eq_ignore_ascii_case(base))
+                .ok_or(format!("{}: unknown base theme {}", name, base))?
+                .clone(),
+            None => Theme::light(),
+        };
+        theme.name = name.to_string();
+        let fields = [
+            ("background", &mut theme.background),
+            ("text", &mut theme.text),
+            ("grid", &mut theme.grid),
+            ("cage", &mut theme.cage),
+            ("error", &mut theme.error),
+            ("active_cell", &mut theme.active_cell),
+            ("highlight", &mut theme.highlight),
+            ("hint", &mut theme.hint),
+            ("hint_target", &mut theme.hint_target),
+            ("overlay", &mut theme.overlay),
+            ("button", &mut theme.button),
+        ];
+        for (key, color) in fields {
+            if let Some(value) = json.get(key) {
+                let text = value.as_str().unwrap_or_default();
+                *color = parse_color(text).ok_or(format!("{}: invalid {} {}", name, key, value))?;
+            }
+        }
+        Ok(theme)
+    }
+}
+
+/// The themes to pick from and the one in use.
+#[derive(Debug, Clone)]
+pub struct Themes {
+    pub themes: Vec<Theme>,
+    pub current: usize,
+}
+
+impl Themes {
+    pub fn builtin() -> Self {
+        Themes {
+            themes: Theme::builtin(),
+            current: 0,
+        }
+    }
+
+    /// The built-in themes and those of a config file, with the one it
+    /// names picked, or the first if there is no such theme.
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        let json: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        let mut themes = Theme::builtin();
+        if let Some(list) = json.get("themes") {
+            for entry in list.as_array().ok_or("themes must be a list")? {
+                let theme = Theme::from_json(entry, &themes)?;
+                // a theme of the same name replaces the earlier one
+                match themes.iter().position(|t| t.name == theme.name) {
+                    Some(i) => themes[i] = theme,
+                    None => themes.push(theme),
+                }
+            }
+        }
+        // a theme picked before it was taken out of the file falls back to
+        // the first one rather than losing the rest of the config
+        let current = json
+            .get("theme")
+            .and_then(Value::as_str)
+            .and_then(|name| {
+                themes
+                    .iter()
+                    .position(|theme| theme.name.eq_ignore_ascii_case(name))
+            })
+            .unwrap_or(0);
+        Ok(Themes { themes, current })
+    }
+
+    /// Reads the config file. The built-in themes stand in when there is
+    /// none, and also when it can't be used, which comes back as the error.
+    pub fn load() -> (Self, Option<String>) {
+        match config_path() {
+            Some(path) => Themes::read(&path),
+            None => (Themes::builtin(), None),
+        }
+    }
+
+    /// Like `load`, from the config file at `path`.
+    pub fn read(path: &Path) -> (Self, Option<String>) {
+        let themes = match fs::read_to_string(path) {
+            Ok(text) => Themes::from_json(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Themes::builtin()),
+            Err(e) => Err(e.to_string()),
+        };
+        match themes {
+            Ok(themes) => (themes, None),
+            Err(e) => (Themes::builtin(), Some(e)),
+        }
+    }
+
+    pub fn current(&self) -> &Theme {
+        &self.themes[self.current]
+    }
+
+    /// Picks the next or previous theme, wrapping around.
+    pub fn cycle(&mut self, step: i32) {
+        let count = self.themes.len() as i32;
+        self.current = (self.current as i32 + step).rem_euclid(count) as usize;
+    }
+
+    /// Writes the theme in use to the config file, keeping the rest of it.
+    pub fn save_choice(&self) -> Result<(), String> {
+        let path = config_path().ok_or("no config directory")?;
+        let mut json = match fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| e.to_string())?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => json!({}),
+            Err(e) => return Err(e.to_string()),
+        };
+        let config = json.as_object_mut().ok_or("config must be an object")?;
+        config.insert("theme".to_string(), json!(self.current().name));
+        let text = serde_json::to_string_pretty(&json).map_err(|e| e.to_string())?;
+        save::write_atomically(&path, &(text + "\n"))
+    }
+}
+
+/// Where the config file is, or `None` when the platform gives no home for
+/// it.
+pub fn config_path() -> Option<PathBuf> {
+    Some(save::user_dir("XDG_CONFIG_HOME", ".config")?.join(CONFIG_FILE))
+}
+
+/// Reads `#rrggbb` or `#rrggbbaa`.
+fn parse_color(text: &str) -> Option<Color> {
+    let hex = text.strip_prefix('#')?;
+    if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    let alpha = if hex.len() == 8 { channel(6)? } else { 255 };
+    Some(Color::RGBA(channel(0)?, channel(2)?, channel(4)?, alpha))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_themes_from_the_config() {
+        let themes = Themes::from_json(
+            r##"{
+                "theme": "sepia",
+                "themes": [
+                    { "name": "Sepia", "base": "Dark", "background": "#f4ecd8", "cage": "#8b5a2b80" },
+                    { "name": "Light", "error": "#00ff00" }
+                ]
+            }"##,
+        )
+        .unwrap();
+        assert_eq!(themes.themes.len(), 5);
+        let sepia = themes.current();
+        assert_eq!(sepia.name, "Sepia");
+        assert_eq!(sepia.background, Color::RGB(0xf4, 0xec, 0xd8));
+        assert_eq!(sepia.cage, Color::RGBA(0x8b, 0x5a, 0x2b, 0x80));
+        // what it leaves out comes from its base
+        assert_eq!(sepia.text, Theme::dark().text);
+        // a built-in theme of the same name is replaced in place
+        assert_eq!(themes.themes[0].name, "Light");
+        assert_eq!(themes.themes[0].error, Color::RGB(0, 255, 0));
+        assert_eq!(themes.themes[0].grid, Theme::light().grid);
+    }
+
+    #[test]
+    fn picks_the_first_theme_when_the_chosen_one_is_gone() {
+        let themes = Themes::from_json(r#"{ "theme": "Sepia" }"#).unwrap();
+        assert_eq!(themes.current, 0);
+        assert_eq!(themes.themes, Theme::builtin());
+    }
+
+    #[test]
+    fn rejects_malformed_colors() {
+        let error = |color: &str| {
+            let text = format!(
+                r#"{{ "themes": [{{ "name": "Bad", "cage": {} }}] }}"#,
+                color
+            );
+            Themes::from_json(&text).err().unwrap()
+        };
+        assert_eq!(error(r##""#12345""##), r##"Bad: invalid cage "#12345""##);
+        assert_eq!(error(r##""123456""##), r##"Bad: invalid cage "123456""##);
+        assert_eq!(error(r##""#gg0000""##), r##"Bad: invalid cage "#gg0000""##);
+        assert_eq!(error(r##""#ééé""##), r##"Bad: invalid cage "#ééé""##);
+        assert_eq!(error("255"), "Bad: invalid cage 255");
+        assert!(Themes::from_json(r#"{ "themes": [{ "base": "Dark" }] }"#).is_err());
+        assert!(Themes::from_json(r#"{ "themes": [{ "name": "X", "base": "Nope" }] }"#).is_err());
+    }
+
+    #[test]
+    fn falls_back_to_the_builtin_themes() {
+        let dir = std::env::temp_dir().join(format!("sumdoku-theme-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(CONFIG_FILE);
+
+        let (themes, error) = Themes::read(&path);
+        assert_eq!(themes.themes, Theme::builtin());
+        assert_eq!(error, None);
+
+        fs::write(&path, "{ \"theme\": ").unwrap();
+        let (themes, error) = Themes::read(&path);
+        assert_eq!(themes.themes, Theme::builtin());
+        assert_eq!(themes.current, 0);
+        assert!(error.is_some());
+
+        fs::write(
+            &path,
+            r#"{ "themes": [{ "name": "Bad", "text": "black" }] }"#,
+        )
+        .unwrap();
+        let (themes, error) = Themes::read(&path);
+        assert_eq!(themes.themes, Theme::builtin());
+        assert_eq!(error.as_deref(), Some(r#"Bad: invalid text "black""#));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,8 @@
+//! Killer sudoku engine: puzzle generation, solving and rating in `logic`,
+//! the state of a game being played in `game`. The SDL front end in
+//! `graphics` is built with the `sdl` feature, on by default; without it
+//! only its themes are, so that the config file can be read and tested.
+
+pub mod game;
+pub mod graphics;
+pub mod logic;
@@ -1,12 +1,32 @@
 use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+
+use super::{rate, solve, solve_within, Difficulty, Shape, Solution};
+
+/// Boards tried for a difficulty on grids up to 9x9. Larger grids take
+/// much longer to generate and get fewer tries, see `difficulty_attempts`.
+const MAX_DIFFICULTY_ATTEMPTS: usize = 50;
+/// Placements tried before filling a grid starts over.
+const FILL_BUDGET: usize = 100_000;
+/// Guesses the solver may make on generated cages before they are thrown
+/// away for new ones. Cages that need many guesses make slow puzzles to
+/// generate and hard ones to play.
+const GENERATION_GUESSES: usize = 50;
+/// Cages split to make a puzzle unique before starting over with new ones.
+const MAX_SPLITS: usize = 20;
 
 #[derive(Debug)]
 pub struct SumdokuBoard {
+    pub shape: Shape,
     pub solution: Vec<Vec<u32>>,
     pub cages: Vec<Cage>,
+    pub difficulty: Difficulty,
+    /// Seed that regenerates this board with `SumdokuBoard::from_seed`, if
+    /// it was generated rather than loaded.
+    pub seed: Option<u64>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Cell {
     pub row: usize,
     pub col: usize,
@@ -37,55 +57,53 @@ impl Cage {
     }
 }
 
-fn is_valid(board: &Vec<Vec<u32>>, row: usize, col: usize, num: u32) -> bool {
+fn is_valid(shape: Shape, board: &[Vec<u32>], row: usize, col: usize, num: u32) -> bool {
     // Check if the number is already in the row
-    for x in 0..9 {
-        if board[row][x] == num {
-            return false;
-        }
+    if board[row].contains(&num) {
+        return false;
     }
 
     // Check if the number is already in the column
-    for x in 0..9 {
-        if board[x][col] == num {
-            return false;
-        }
+    if board.iter().any(|r| r[col] == num) {
+        return false;
     }
 
-    // Check if the number is in the 3x3 subgrid
-    let start_row = 3 * (row / 3);
-    let start_col = 3 * (col / 3);
-    for i in 0..3 {
-        for j in 0..3 {
-            if board[i + start_row][j + start_col] == num {
-                return false;
-            }
+    // Check if the number is in the box
+    let start_row = shape.box_rows * (row / shape.box_rows);
+    let start_col = shape.box_cols * (col / shape.box_cols);
+    for r in &board[start_row..start_row + shape.box_rows] {
+        if r[start_col..start_col + shape.box_cols].contains(&num) {
+            return false;
         }
     }
 
     true
 }
 
-fn find_empty_cell(b: &Vec<Vec<u32>>) -> Option<(usize, usize)> {
-    for r in 0..9 {
-        for c in 0..9 {
-            if b[r][c] == 0 {
-                return Some((r, c));
-            }
+fn find_empty_cell(b: &[Vec<u32>]) -> Option<(usize, usize)> {
+    for (r, row) in b.iter().enumerate() {
+        if let Some(c) = row.iter().position(|&v| v == 0) {
+            return Some((r, c));
         }
     }
     None
 }
 
-fn solve(b: &mut Vec<Vec<u32>>, rng: &mut ThreadRng) -> bool {
+/// Fills the grid with random digits, giving up once `budget` placements
+/// have been tried so that an unlucky start does not search forever.
+fn fill_grid(shape: Shape, b: &mut [Vec<u32>], rng: &mut ChaCha8Rng, budget: &mut usize) -> bool {
     if let Some((row, col)) = find_empty_cell(b) {
-        let mut numbers: Vec<u32> = (1..=9).collect();
-        numbers.shuffle(&mut thread_rng());
+        let mut numbers: Vec<u32> = (1..=shape.size as u32).collect();
+        numbers.shuffle(rng);
 
         for &num in numbers.iter() {
-            if is_valid(b, row, col, num) {
+            if *budget == 0 {
+                return false;
+            }
+            if is_valid(shape, b, row, col, num) {
+                *budget -= 1;
                 b[row][col] = num;
-                if solve(b, rng) {
+                if fill_grid(shape, b, rng, budget) {
                     return true;
                 }
                 b[row][col] = 0; // Backtrack
@@ -96,17 +114,25 @@ fn solve(b: &mut Vec<Vec<u32>>, rng: &mut ThreadRng) -> bool {
     true
 }
 
-fn generate_solution(rng: &mut ThreadRng) -> Vec<Vec<u32>> {
-    let mut board = vec![vec![0u32; 9]; 9];
-    solve(&mut board, rng);
-    board
+fn generate_solution(shape: Shape, rng: &mut ChaCha8Rng) -> Vec<Vec<u32>> {
+    loop {
+        let mut board = vec![vec![0u32; shape.size]; shape.size];
+        let mut budget = FILL_BUDGET;
+        if fill_grid(shape, &mut board, rng, &mut budget) {
+            return board;
+        }
+    }
 }
 
-fn generate_cages(max_cage_size: usize) -> Vec<Cage> {
-    let mut total = 81;
-    let mut rng = rand::thread_rng();
+fn generate_cages(
+    solution: &[Vec<u32>],
+    max_cage_size: usize,
+    rng: &mut ChaCha8Rng,
+) -> Vec<Cage> {
+    let size = solution.len();
+    let mut total = size * size;
     let mut result = Vec::new();
-    let mut visited = vec![vec![false; 9]; 9];
+    let mut visited = vec![vec![false; size]; size];
     let dirs = [(-1i32, 0i32), (1, 0), (0, -1), (0, 1)];
     while total > 0 {
         let mut cells = Vec::new();
@@ -118,17 +144,17 @@ fn generate_cages(max_cage_size: usize) -> Vec<Cage> {
         let c = y as usize;
         cells.push(Cell::new(r, c));
         visited[r][c] = true;
-        let approx_size: usize = rng.gen::<usize>() % max_cage_size;
+        let approx_size: usize = rng.gen_range(0..max_cage_size);
         for _i in 0..approx_size {
-            let dir = rng.gen::<usize>() % 4;
+            let dir = rng.gen_range(0..4);
             let (dr, dc) = dirs[dir];
             let (nr, nc) = (x + dr, y + dc);
-            if try_assign(nr, nc, &mut visited, &mut cells) {
+            if try_assign(nr, nc, solution, &mut visited, &mut cells) {
                 continue;
             }
             let mut added = false;
             for (dr, dc) in dirs {
-                if try_assign(x + dr, y + dc, &mut visited, &mut cells) {
+                if try_assign(x + dr, y + dc, solution, &mut visited, &mut cells) {
                     added = true;
                     x += dr;
                     y += dc;
@@ -154,8 +180,20 @@ fn generate_cages(max_cage_size: usize) -> Vec<Cage> {
     result
 }
 
-fn try_assign(r: i32, c: i32, v: &mut Vec<Vec<bool>>, ce: &mut Vec<Cell>) -> bool {
-    if r >= 0 && r < 9 && c >= 0 && c < 9 && !v[r as usize][c as usize] {
+fn try_assign(
+    r: i32,
+    c: i32,
+    solution: &[Vec<u32>],
+    v: &mut [Vec<bool>],
+    ce: &mut Vec<Cell>,
+) -> bool {
+    let size = v.len() as i32;
+    if (0..size).contains(&r) && (0..size).contains(&c) && !v[r as usize][c as usize] {
+        // a digit may not repeat within a cage
+        let digit = solution[r as usize][c as usize];
+        if ce.iter().any(|cell| solution[cell.row][cell.col] == digit) {
+            return false;
+        }
         ce.push(Cell::new(r as usize, c as usize));
         v[r as usize][c as usize] = true;
         return true;
@@ -163,19 +201,17 @@ fn try_assign(r: i32, c: i32, v: &mut Vec<Vec<bool>>, ce: &mut Vec<Cell>) -> boo
     false
 }
 
-fn find_available_cell(visited: &Vec<Vec<bool>>) -> (i32, i32) {
-    for r in 0..9 {
-        for c in 0..9 {
-            if !visited[r][c] {
-                return (r as i32, c as i32);
-            }
+fn find_available_cell(visited: &[Vec<bool>]) -> (i32, i32) {
+    for (r, row) in visited.iter().enumerate() {
+        if let Some(c) = row.iter().position(|&v| !v) {
+            return (r as i32, c as i32);
         }
     }
     (-1, -1)
 }
 
-fn add_cages(board: &mut SumdokuBoard, max_cage_size: usize) {
-    for cage in generate_cages(max_cage_size) {
+fn add_cages(board: &mut SumdokuBoard, max_cage_size: usize, rng: &mut ChaCha8Rng) {
+    for cage in generate_cages(&board.solution, max_cage_size, rng) {
         let mut sum = 0;
         for cell in &cage.cells {
             sum += board.solution[cell.row][cell.col];
@@ -189,14 +225,407 @@ fn add_cages(board: &mut SumdokuBoard, max_cage_size: usize) {
 }
 
 impl SumdokuBoard {
-    pub fn new(max_cage_size: usize) -> Self {
-        let mut rng = rand::thread_rng();
-        let solution = generate_solution(&mut rng);
+    pub fn new(shape: Shape, max_cage_size: usize) -> Self {
+        SumdokuBoard::from_seed(thread_rng().gen(), shape, max_cage_size)
+    }
+
+    /// Generates a board from a seed. The same seed, shape and cage size
+    /// always give the same grid and cages.
+    pub fn from_seed(seed: u64, shape: Shape, max_cage_size: usize) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let solution = generate_solution(shape, &mut rng);
         let mut board = SumdokuBoard {
+            shape,
             solution,
             cages: Vec::new(),
+            difficulty: Difficulty::Easy,
+            seed: Some(seed),
         };
-        add_cages(&mut board, max_cage_size);
+        add_cages(&mut board, max_cage_size, &mut rng);
+        let mut splits = 0;
+        loop {
+            match solve_within(&board, GENERATION_GUESSES) {
+                Some(Solution::Unique(_)) => break,
+                Some(Solution::Multiple(first, second)) if splits < MAX_SPLITS => {
+                    let (row, col) = find_ambiguous_cell(&board.solution, &first, &second);
+                    board.split_cage(row, col);
+                    splits += 1;
+                }
+                // no solution, too slow to solve or still ambiguous: new cages
+                _ => {
+                    board.cages.clear();
+                    add_cages(&mut board, max_cage_size, &mut rng);
+                    splits = 0;
+                }
+            }
+        }
+        board.difficulty = rate(&board);
         board
     }
+
+    /// Generates boards until one matches the requested difficulty. Gives up
+    /// after a number of attempts, fewer on larger grids, and returns the
+    /// closest one found.
+    pub fn with_difficulty(shape: Shape, max_cage_size: usize, difficulty: Difficulty) -> Self {
+        SumdokuBoard::from_seed_with_difficulty(
+            thread_rng().gen(),
+            shape,
+            max_cage_size,
+            difficulty,
+        )
+    }
+
+    /// Seeded variant of `with_difficulty`. Every attempt draws its own seed
+    /// from the given one, so the result is reproducible both ways.
+    pub fn from_seed_with_difficulty(
+        seed: u64,
+        shape: Shape,
+        max_cage_size: usize,
+        difficulty: Difficulty,
+    ) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let distance = |board: &SumdokuBoard| (board.difficulty as i32 - difficulty as i32).abs();
+        let mut best = SumdokuBoard::from_seed(rng.gen(), shape, max_cage_size);
+        for _ in 1..difficulty_attempts(shape) {
+            if best.difficulty == difficulty {
+                break;
+            }
+            let board = SumdokuBoard::from_seed(rng.gen(), shape, max_cage_size);
+            if distance(&board) < distance(&best) {
+                best = board;
+            }
+        }
+        best
+    }
+
+    /// Builds a board from cages alone, filling in the solution by solving.
+    /// Fails unless the cages lead to exactly one grid.
+    pub fn from_cages(shape: Shape, cages: Vec<Cage>) -> Result<Self, String> {
+        let mut board = SumdokuBoard {
+            shape,
+            solution: Vec::new(),
+            cages,
+            difficulty: Difficulty::Easy,
+            seed: None,
+        };
+        board.check_cage_sizes()?;
+        match solve(&board) {
+            Solution::Unique(solution) => board.solution = solution,
+            Solution::None => return Err("puzzle has no solution".to_string()),
+            Solution::Multiple(..) => return Err("puzzle has more than one solution".to_string()),
+        }
+        board.difficulty = rate(&board);
+        Ok(board)
+    }
+
+    /// Builds a board from cages and a known solution, checking that they
+    /// agree.
+    pub fn from_solution(
+        shape: Shape,
+        cages: Vec<Cage>,
+        solution: Vec<Vec<u32>>,
+    ) -> Result<Self, String> {
+        let mut board = SumdokuBoard {
+            shape,
+            solution,
+            cages,
+            difficulty: Difficulty::Easy,
+            seed: None,
+        };
+        board.validate()?;
+        board.difficulty = rate(&board);
+        Ok(board)
+    }
+
+    /// Takes the cell out of its cage into a cage of its own. Whatever is left
+    /// of the old cage is split into connected parts, each with its own sum.
+    pub fn split_cage(&mut self, row: usize, col: usize) {
+        let Some(index) = self
+            .cages
+            .iter()
+            .position(|cage| cage.cells.iter().any(|c| c.row == row && c.col == col))
+        else {
+            return;
+        };
+        let mut cage = self.cages.remove(index);
+        let mut rest: Vec<Cell> = std::mem::take(&mut cage.cells)
+            .into_iter()
+            .filter(|c| c.row != row || c.col != col)
+            .collect();
+        self.push_cage(vec![Cell::new(row, col)]);
+        while let Some(start) = rest.pop() {
+            let mut part = vec![start];
+            let mut i = 0;
+            while i < part.len() {
+                let (adjacent, other): (Vec<Cell>, Vec<Cell>) = rest
+                    .into_iter()
+                    .partition(|c| cage.is_adjacent(&part[i], c));
+                part.extend(adjacent);
+                rest = other;
+                i += 1;
+            }
+            part.sort_by_key(|c| c.row * 100 + c.col);
+            self.push_cage(part);
+        }
+    }
+
+    /// Checks the board against the killer rules: the solution is a proper
+    /// sudoku grid, every cell lies on the grid and in at most one cage, no
+    /// digit repeats within a cage and each sum matches the solution.
+    pub fn validate(&self) -> Result<(), String> {
+        let size = self.shape.size;
+        if self.solution.len() != size || self.solution.iter().any(|row| row.len() != size) {
+            return Err(format!("solution is not a {}x{} grid", size, size));
+        }
+        if self
+            .solution
+            .iter()
+            .flatten()
+            .any(|&v| !(1..=size as u32).contains(&v))
+        {
+            return Err(format!("solution holds a digit outside 1-{}", size));
+        }
+        for i in 0..size {
+            let (mut row, mut col, mut square) = (0u16, 0u16, 0u16);
+            let (box_row, box_col) = self.shape.box_origin(i);
+            for j in 0..size {
+                row |= 1 << self.solution[i][j];
+                col |= 1 << self.solution[j][i];
+                square |= 1 << self.solution[box_row + j / self.shape.box_cols]
+                    [box_col + j % self.shape.box_cols];
+            }
+            let full = (1..=size).fold(0, |acc, d| acc | 1 << d);
+            if row != full {
+                return Err(format!("row {} does not hold every digit once", i + 1));
+            }
+            if col != full {
+                return Err(format!("column {} does not hold every digit once", i + 1));
+            }
+            if square != full {
+                return Err(format!("box {} does not hold every digit once", i + 1));
+            }
+        }
+        self.check_cage_sizes()?;
+        let mut caged = vec![vec![false; size]; size];
+        for (i, cage) in self.cages.iter().enumerate() {
+            let mut seen = 0u16;
+            let mut sum = 0;
+            for cell in &cage.cells {
+                if cell.row >= size || cell.col >= size {
+                    return Err(format!(
+                        "cage {} has cell r{}c{} outside the grid",
+                        i + 1,
+                        cell.row + 1,
+                        cell.col + 1
+                    ));
+                }
+                if caged[cell.row][cell.col] {
+                    return Err(format!(
+                        "cell r{}c{} belongs to more than one cage",
+                        cell.row + 1,
+                        cell.col + 1
+                    ));
+                }
+                caged[cell.row][cell.col] = true;
+                let digit = self.solution[cell.row][cell.col];
+                if seen & 1 << digit != 0 {
+                    return Err(format!(
+                        "digit {} repeats in cage {} at r{}c{}",
+                        digit,
+                        i + 1,
+                        cell.row + 1,
+                        cell.col + 1
+                    ));
+                }
+                seen |= 1 << digit;
+                sum += digit;
+            }
+            if sum != cage.sum {
+                return Err(format!(
+                    "cage {} sums to {} but is labelled {}",
+                    i + 1,
+                    sum,
+                    cage.sum
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// A cage can't hold more cells than there are digits, as none may repeat.
+    fn check_cage_sizes(&self) -> Result<(), String> {
+        let size = self.shape.size;
+        match self.cages.iter().position(|cage| cage.cells.len() > size) {
+            Some(i) => Err(format!(
+                "cage {} has {} cells but only {} digits can go in it",
+                i + 1,
+                self.cages[i].cells.len(),
+                size
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn push_cage(&mut self, cells: Vec<Cell>) {
+        let sum = cells.iter().map(|c| self.solution[c.row][c.col]).sum();
+        self.cages.push(Cage {
+            sum,
+            cells,
+            lines: None,
+        });
+    }
+}
+
+/// Boards `from_seed_with_difficulty` tries. Generating gets steeply slower
+/// as grids grow, so the tries fall with the cube of the cell count.
+fn difficulty_attempts(shape: Shape) -> usize {
+    let classic = Shape::CLASSIC.cell_count().pow(3);
+    let attempts = MAX_DIFFICULTY_ATTEMPTS * classic / shape.cell_count().pow(3);
+    attempts.clamp(1, MAX_DIFFICULTY_ATTEMPTS)
+}
+
+/// A cell where one of two distinct solutions disagrees with the intended grid.
+fn find_ambiguous_cell(
+    solution: &[Vec<u32>],
+    first: &[Vec<u32>],
+    second: &[Vec<u32>],
+) -> (usize, usize) {
+    let other = if first == solution { second } else { first };
+    for (r, row) in other.iter().enumerate() {
+        for (c, &value) in row.iter().enumerate() {
+            if value != solution[r][c] {
+                return (r, c);
+            }
+        }
+    }
+    (0, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_the_same_board() {
+        for shape in Shape::SUPPORTED {
+            let board = SumdokuBoard::from_seed(7, shape, 5);
+            let again = SumdokuBoard::from_seed(7, shape, 5);
+            assert_eq!(board.solution, again.solution);
+            assert_eq!(board.to_text(), again.to_text());
+            assert_eq!(board.difficulty, again.difficulty);
+            assert_eq!(board.seed, Some(7));
+        }
+        let other = SumdokuBoard::from_seed(8, Shape::CLASSIC, 5);
+        assert_ne!(other.to_text(), SumdokuBoard::from_seed(7, Shape::CLASSIC, 5).to_text());
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_board_for_a_difficulty() {
+        let shape = Shape::new(2, 3);
+        let board = SumdokuBoard::from_seed_with_difficulty(3, shape, 6, Difficulty::Hard);
+        let again = SumdokuBoard::from_seed_with_difficulty(3, shape, 6, Difficulty::Hard);
+        assert_eq!(board.to_text(), again.to_text());
+    }
+
+    #[test]
+    fn generated_cages_follow_the_rules() {
+        for seed in 0..20 {
+            let board = SumdokuBoard::from_seed(seed, Shape::CLASSIC, 4);
+            assert_eq!(board.validate(), Ok(()));
+            assert!(board.cages.iter().all(|cage| cage.cells.len() <= 4));
+        }
+    }
+
+    /// A 4x4 board over a fixed grid with the given cages.
+    fn small_board(cages: &[(u32, &[(usize, usize)])]) -> SumdokuBoard {
+        SumdokuBoard {
+            shape: Shape::new(2, 2),
+            solution: vec![
+                vec![1, 2, 3, 4],
+                vec![3, 4, 1, 2],
+                vec![2, 1, 4, 3],
+                vec![4, 3, 2, 1],
+            ],
+            cages: cages
+                .iter()
+                .map(|&(sum, cells)| Cage {
+                    sum,
+                    cells: cells.iter().map(|&(r, c)| Cell::new(r, c)).collect(),
+                    lines: None,
+                })
+                .collect(),
+            difficulty: Difficulty::Easy,
+            seed: None,
+        }
+    }
+
+    #[test]
+    fn validate_checks_cages_against_the_solution() {
+        let boxes: [&[(usize, usize)]; 4] = [
+            &[(0, 0), (0, 1), (1, 0), (1, 1)],
+            &[(0, 2), (0, 3), (1, 2), (1, 3)],
+            &[(2, 0), (2, 1), (3, 0), (3, 1)],
+            &[(2, 2), (2, 3), (3, 2), (3, 3)],
+        ];
+        let valid = small_board(&boxes.map(|cells| (10, cells)));
+        assert_eq!(valid.validate(), Ok(()));
+        let repeated = small_board(&[(9, &[(1, 1), (1, 2), (2, 2)])]);
+        assert_eq!(
+            repeated.validate(),
+            Err("digit 4 repeats in cage 1 at r3c3".to_string())
+        );
+        let wrong_sum = small_board(&[(10, boxes[0]), (11, boxes[1])]);
+        assert_eq!(
+            wrong_sum.validate(),
+            Err("cage 2 sums to 10 but is labelled 11".to_string())
+        );
+        let mut not_a_sudoku = small_board(&[]);
+        not_a_sudoku.solution.swap(1, 2);
+        assert_eq!(
+            not_a_sudoku.validate(),
+            Err("box 1 does not hold every digit once".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_a_cage_with_more_cells_than_digits() {
+        let shape = Shape::new(2, 2);
+        let cage = || Cage {
+            sum: 40,
+            cells: (0..16).map(|i| Cell::new(i / 4, i % 4)).collect(),
+            lines: None,
+        };
+        let error = "cage 1 has 16 cells but only 4 digits can go in it".to_string();
+        assert_eq!(SumdokuBoard::from_cages(shape, vec![cage()]).err(), Some(error.clone()));
+        let solution = vec![
+            vec![1, 2, 3, 4],
+            vec![3, 4, 1, 2],
+            vec![2, 1, 4, 3],
+            vec![4, 3, 2, 1],
+        ];
+        let board = SumdokuBoard::from_solution(shape, vec![cage()], solution);
+        assert_eq!(board.err(), Some(error));
+    }
+
+    #[test]
+    fn generation_stays_fast_on_every_size() {
+        // seeds 3, 4 and 8 once took minutes on a 12x12 grid
+        for shape in Shape::SUPPORTED {
+            for seed in 1..=8 {
+                let start = Instant::now();
+                let board = SumdokuBoard::from_seed(seed, shape, 6);
+                let elapsed = start.elapsed();
+                assert!(
+                    elapsed < Duration::from_secs(20),
+                    "{} seed {} took {:?}",
+                    shape,
+                    seed,
+                    elapsed
+                );
+                assert_eq!(board.validate(), Ok(()));
+            }
+        }
+    }
 }
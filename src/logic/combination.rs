@@ -0,0 +1,247 @@
+//! Digit combinations for cages. A combination is a set of distinct digits,
+//! written as a bit mask where bit `d` stands for digit `d`, the same way
+//! notes are kept.
+
+use super::{Cage, Shape};
+
+impl Cage {
+    /// Every set of distinct digits that fills the cage and adds up to its
+    /// sum, ignoring what is already on the grid.
+    pub fn combinations(&self, shape: Shape) -> Vec<u16> {
+        combinations(self.sum, self.cells.len(), shape.size as u32)
+    }
+
+    /// The combinations still open given the cells' contents. `placed` and
+    /// `excluded` follow the order of `cells`: `placed` holds the digit in
+    /// each cell or 0, `excluded` a mask of digits ruled out for it. A
+    /// combination stays when its digits can be spread over the cells so
+    /// that every placed digit is kept and no excluded digit is used.
+    pub fn possible_combinations(
+        &self,
+        shape: Shape,
+        placed: &[u32],
+        excluded: &[u16],
+    ) -> Vec<u16> {
+        let all = combination_mask(1..=shape.size as u32);
+        let candidates: Vec<u16> = (0..self.cells.len())
+            .map(|i| match placed.get(i) {
+                Some(&value) if value != 0 => 1 << value,
+                _ => all & !excluded.get(i).copied().unwrap_or(0),
+            })
+            .collect();
+        fitting_combinations(self.sum, &candidates, shape.size as u32)
+    }
+}
+
+/// All sets of `count` distinct digits from 1..=max_digit adding up to `sum`.
+pub fn combinations(sum: u32, count: usize, max_digit: u32) -> Vec<u16> {
+    fn collect(from: u32, max: u32, sum: u32, count: usize, mask: u16, out: &mut Vec<u16>) {
+        if count == 0 {
+            if sum == 0 {
+                out.push(mask);
+            }
+            return;
+        }
+        for d in from..=max {
+            if d > sum {
+                break;
+            }
+            collect(d + 1, max, sum - d, count - 1, mask | 1 << d, out);
+        }
+    }
+    let mut out = Vec::new();
+    collect(1, max_digit, sum, count, 0, &mut out);
+    out
+}
+
+/// The digits of a combination in increasing order.
+pub fn digits(mask: u16) -> impl Iterator<Item = u32> {
+    (1..16).filter(move |d| mask & 1 << d != 0)
+}
+
+/// Builds a combination from its digits.
+pub fn combination_mask(digits: impl IntoIterator<Item = u32>) -> u16 {
+    digits.into_iter().fold(0, |acc, d| acc | 1 << d)
+}
+
+/// What the digits of a combination add up to.
+pub fn combination_sum(mut mask: u16) -> u32 {
+    let mut sum = 0;
+    while mask != 0 {
+        sum += mask.trailing_zeros();
+        mask &= mask - 1;
+    }
+    sum
+}
+
+/// Combinations for a cage whose cells hold the given candidates, keeping
+/// only those that can be spread over the cells one digit each.
+pub(super) fn fitting_combinations(sum: u32, candidates: &[u16], max_digit: u32) -> Vec<u16> {
+    combinations(sum, candidates.len(), max_digit)
+        .into_iter()
+        .filter(|&combination| {
+            assignable(combination, candidates)
+                .iter()
+                .all(|&found| found != 0)
+        })
+        .collect()
+}
+
+/// Which digits of `combination` each cell can take in a complete assignment
+/// that uses every digit of the combination exactly once.
+fn assignable(combination: u16, candidates: &[u16]) -> Vec<u16> {
+    let digits: Vec<u32> = digits(combination).collect();
+    let n = candidates.len();
+    let full = (1usize << n) - 1;
+    // forward[i][m]: cells 0..i can take exactly the digits in m
+    let mut forward = vec![vec![false; full + 1]; n + 1];
+    forward[0][0] = true;
+    for i in 0..n {
+        for m in 0..=full {
+            if !forward[i][m] {
+                continue;
+            }
+            for (k, &d) in digits.iter().enumerate() {
+                if m & 1 << k == 0 && candidates[i] & 1 << d != 0 {
+                    forward[i + 1][m | 1 << k] = true;
+                }
+            }
+        }
+    }
+    // backward[i][m]: cells i..n can take exactly the digits not in m
+    let mut backward = vec![vec![false; full + 1]; n + 1];
+    backward[n][full] = true;
+    for i in (0..n).rev() {
+        for m in 0..=full {
+            for (k, &d) in digits.iter().enumerate() {
+                if m & 1 << k == 0 && candidates[i] & 1 << d != 0 && backward[i + 1][m | 1 << k] {
+                    backward[i][m] = true;
+                    break;
+                }
+            }
+        }
+    }
+    let mut found = vec![0u16; n];
+    for i in 0..n {
+        for m in 0..=full {
+            if !forward[i][m] {
+                continue;
+            }
+            for (k, &d) in digits.iter().enumerate() {
+                if m & 1 << k == 0 && candidates[i] & 1 << d != 0 && backward[i + 1][m | 1 << k] {
+                    found[i] |= 1 << d;
+                }
+            }
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::Cell;
+
+    /// A cage over the first `cells` cells of the top row.
+    fn cage(sum: u32, cells: usize) -> Cage {
+        Cage {
+            sum,
+            cells: (0..cells).map(|col| Cell::new(0, col)).collect(),
+            lines: None,
+        }
+    }
+
+    fn masks(combinations: &[&[u32]]) -> Vec<u16> {
+        combinations
+            .iter()
+            .map(|digits| combination_mask(digits.iter().copied()))
+            .collect()
+    }
+
+    #[test]
+    fn lists_the_combinations_of_a_cage() {
+        let classic = Shape::CLASSIC;
+        assert_eq!(cage(17, 2).combinations(classic), masks(&[&[8, 9]]));
+        assert_eq!(cage(23, 3).combinations(classic), masks(&[&[6, 8, 9]]));
+        assert_eq!(
+            cage(10, 2).combinations(classic),
+            masks(&[&[1, 9], &[2, 8], &[3, 7], &[4, 6]])
+        );
+        assert_eq!(
+            cage(45, 9).combinations(classic),
+            masks(&[&[1, 2, 3, 4, 5, 6, 7, 8, 9]])
+        );
+        assert!(cage(2, 2).combinations(classic).is_empty());
+    }
+
+    #[test]
+    fn keeps_to_the_digits_of_the_grid_size() {
+        assert_eq!(
+            cage(10, 4).combinations(Shape::new(2, 2)),
+            masks(&[&[1, 2, 3, 4]])
+        );
+        assert_eq!(
+            cage(11, 2).combinations(Shape::new(2, 3)),
+            masks(&[&[5, 6]])
+        );
+        assert!(cage(17, 2).combinations(Shape::new(2, 3)).is_empty());
+        assert_eq!(
+            cage(23, 2).combinations(Shape::new(3, 4)),
+            masks(&[&[11, 12]])
+        );
+    }
+
+    #[test]
+    fn possible_combinations_follow_placed_and_excluded_digits() {
+        let classic = Shape::CLASSIC;
+        let ten = cage(10, 2);
+        assert_eq!(
+            ten.possible_combinations(classic, &[3, 0], &[0, 0]),
+            masks(&[&[3, 7]])
+        );
+        // 9 ruled out of both cells leaves no room for 1 + 9
+        let no_nine = 1 << 9;
+        assert_eq!(
+            ten.possible_combinations(classic, &[0, 0], &[no_nine, no_nine]),
+            masks(&[&[2, 8], &[3, 7], &[4, 6]])
+        );
+        // a placed digit stands even where it is excluded
+        assert_eq!(
+            ten.possible_combinations(classic, &[4, 0], &[1 << 4, 0]),
+            masks(&[&[4, 6]])
+        );
+        // with 6 ruled out of the other cell, nothing goes with a placed 4
+        assert!(ten
+            .possible_combinations(classic, &[4, 0], &[0, 1 << 6])
+            .is_empty());
+        let small = cage(7, 2);
+        assert_eq!(
+            small.possible_combinations(Shape::new(2, 2), &[0, 0], &[0, 0]),
+            masks(&[&[3, 4]])
+        );
+    }
+
+    #[test]
+    fn fitting_combinations_spread_over_the_candidates() {
+        let low = combination_mask([1, 2, 3]);
+        let high = combination_mask([7, 8, 9]);
+        assert_eq!(
+            fitting_combinations(10, &[low, high], 9),
+            masks(&[&[1, 9], &[2, 8], &[3, 7]])
+        );
+        // both cells want 1, so 1 + 2 + 3 can't be spread over them
+        let one = combination_mask([1]);
+        assert!(fitting_combinations(6, &[one, one, low], 9).is_empty());
+        assert_eq!(
+            fitting_combinations(6, &[one, low, low], 9),
+            masks(&[&[1, 2, 3]])
+        );
+    }
+
+    #[test]
+    fn reads_and_adds_up_masks() {
+        let mask = combination_mask([2, 5, 11]);
+        assert_eq!(digits(mask).collect::<Vec<_>>(), vec![2, 5, 11]);
+        assert_eq!(combination_sum(mask), 18);
+    }
+}
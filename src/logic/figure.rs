@@ -118,7 +118,7 @@ impl Edge {
     }
 
     fn align(&mut self) {
-        if self.x==0 {
+        if self.h != 0 {
             let y = if self.h==1 { self.min(&self.start.y, &self.end.y) } else { self.max(&self.start.y,& self.end.y) };
             self.start.y = y;
             self.end.y = y;            
@@ -144,36 +144,51 @@ pub struct Figure {
 }
 
 impl Figure {
-    pub fn from_lines(cell_size: usize, padding: i32, lines: &Vec<Line>) -> Self {
-        let area_size = cell_size*9;
-        let mut points = vec![vec![(0i8, 0i8); area_size+1];area_size+1];
+    /// Insets an outline made of `lines` by `padding`, moving every edge
+    /// towards the inside of the shape. The lines are the cell sides facing
+    /// out of the shape, as given by `Line::outline`.
+    pub fn from_lines(padding: i32, lines: &[Line]) -> Self {
         let mut edges = Vec::with_capacity(lines.len());
         for line in lines {
-            let (h,v) = get_hv(line);
-            apply_hv(line.x1 as usize, line.y1 as usize, (h,v), &mut points);
-            apply_hv(line.x2 as usize, line.y2 as usize, (h,v), &mut points);
-            edges.push(Edge::from_line(line));
-        }
-        for edge in edges.iter_mut() {
-            apply_hv_to_point(&mut edge.start, &points);
-            apply_hv_to_point(&mut edge.end, &points);
-            edge.start.apply_padding(padding);
-            edge.end.apply_padding(padding);
+            let mut edge = Edge::from_line(line);
+            for point in [&mut edge.start, &mut edge.end] {
+                let (h, v) = corner(line, point, lines);
+                point.h = h;
+                point.v = v;
+                point.apply_padding(padding);
+            }
             edge.align();
+            edges.push(edge);
         }
         Figure { edges }
     }
 }
 
-fn apply_hv(x: usize, y: usize, (h,v) :(i8,i8),  points: &mut Vec<Vec<(i8,i8)>>) {
-    let (th,tv) = points[x][y];
-    points[x][y] = (if h != 0 { h } else { th }, if v != 0 { v } else { tv });
-}
-
-fn apply_hv_to_point(p: &mut Point, points: &Vec<Vec<(i8,i8)>>) {
-    let (h,v) = points[p.x as usize][p.y as usize];
-    p.h = h;
-    p.v = v;
+/// Which way the corner at one end of `line` points: the inward sides of
+/// `line` and of the line across it meeting there. Where two corners touch
+/// only at that point, the one on the same side as `line` is taken.
+fn corner(line: &Line, point: &Point, lines: &[Line]) -> (i8, i8) {
+    let (h, v) = get_hv(line);
+    let at = (point.x as u32, point.y as u32);
+    // the side of the point a line lies on, as a step along its axis
+    let side = |l: &Line| if (l.x1, l.y1) == at { 1 } else { -1 };
+    let mut found = (0, 0);
+    for other in lines {
+        let (oh, ov) = get_hv(other);
+        let touches = (other.x1, other.y1) == at || (other.x2, other.y2) == at;
+        if (oh != 0) == (h != 0) || !touches {
+            continue;
+        }
+        let same_quadrant = if h != 0 {
+            side(line) == ov && side(other) == h
+        } else {
+            side(line) == oh && side(other) == v
+        };
+        if found == (0, 0) || same_quadrant {
+            found = (oh, ov);
+        }
+    }
+    (h + found.0, v + found.1)
 }
 
 fn get_hv(line: &Line) -> (i8, i8) {
@@ -232,10 +247,10 @@ impl Line {
         let t = (if st { size * c } else { size * (c + 1) }) as u32;
         if v {
             return Line {
-                x1: t,
-                x2: t,
-                y1: s,
-                y2: e,
+                x1: s,
+                x2: e,
+                y1: t,
+                y2: t,
                 loc: if st {
                     LineLocation::Top
                 } else {
@@ -244,10 +259,10 @@ impl Line {
             };
         }
         Line {
-            x1: s,
-            x2: e,
-            y1: t,
-            y2: t,
+            x1: t,
+            x2: t,
+            y1: s,
+            y2: e,
             loc: if st {
                 LineLocation::Left
             } else {
@@ -265,6 +280,38 @@ impl Line {
         lines
     }
 
+    /// The sides of `cells` facing out of the group, each run of sides in
+    /// a straight line joined into one.
+    pub fn outline(size: usize, cells: &[Cell]) -> Vec<Line> {
+        let mut lines: Vec<Line> = Vec::new();
+        for cell in cells {
+            for line in Line::from_cell(size, cell) {
+                // a side two cells share is inside the group
+                match lines.iter().position(|other| *other == line) {
+                    Some(i) => {
+                        lines.remove(i);
+                    }
+                    None => lines.push(line),
+                }
+            }
+        }
+        let mut i = 0;
+        while i < lines.len() {
+            let joined = (i + 1..lines.len()).find_map(|j| {
+                let line = lines[i].merge(&lines[j]).filter(|l| l.loc == lines[j].loc)?;
+                Some((j, line))
+            });
+            match joined {
+                Some((j, line)) => {
+                    lines[i] = line;
+                    lines.remove(j);
+                }
+                None => i += 1,
+            }
+        }
+        lines
+    }
+
     pub fn is_connected(&self, other: &Line) -> Option<(u32, u32, u32, u32)> {
         if self.x1 == other.x1 && self.y1 == other.y1 {
             Some((self.x2, self.y2, other.x2, other.y2))
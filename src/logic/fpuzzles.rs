@@ -0,0 +1,237 @@
+//! Killer cages in the JSON used by f-puzzles and SudokuPad, after
+//! decompression.
+//!
+//! f-puzzles lists cages under `killercage`, with cells written as `"R1C1"`
+//! and the sum as a string `value`. SudokuPad lists them under `cages`, with
+//! cells either in the same notation or as zero based `[row, col]` pairs.
+//! Given digits outside cages become single cell cages. Both formats leave
+//! out the solution, so importing solves the puzzle.
+
+use serde_json::{json, Value};
+
+use super::{Cage, Cell, Shape, SumdokuBoard};
+
+impl SumdokuBoard {
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        let json: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        let shape = match json.get("size") {
+            None => Shape::CLASSIC,
+            Some(size) => size
+                .as_u64()
+                .and_then(|size| Shape::for_size(size as usize))
+                .ok_or(format!("unsupported grid size {}", size))?,
+        };
+        let size = shape.size;
+        let (key, grid_key) = if json.get("killercage").is_some() {
+            ("killercage", "grid")
+        } else {
+            ("cages", "cells")
+        };
+        let mut cages = Vec::new();
+        let mut caged = vec![vec![false; size]; size];
+        let list = json.get(key).and_then(Value::as_array);
+        for (i, entry) in list.into_iter().flatten().enumerate() {
+            let style = entry.get("style").and_then(Value::as_str);
+            if style.is_some_and(|s| s != "killer") {
+                continue;
+            }
+            let Some(cage) =
+                parse_cage(entry, size).map_err(|e| format!("{} {}: {}", key, i, e))?
+            else {
+                continue;
+            };
+            for cell in &cage.cells {
+                if caged[cell.row][cell.col] {
+                    return Err(format!(
+                        "r{}c{} is in more than one cage",
+                        cell.row + 1,
+                        cell.col + 1
+                    ));
+                }
+                caged[cell.row][cell.col] = true;
+            }
+            cages.push(cage);
+        }
+        let rows = json.get(grid_key).and_then(Value::as_array);
+        for (r, row) in rows.into_iter().flatten().enumerate().take(size) {
+            let row = row.as_array().into_iter().flatten();
+            for (c, cell) in row.enumerate().take(size) {
+                let given = cell.get("given").and_then(Value::as_bool).unwrap_or(true);
+                let Some(value) = cell.get("value").and_then(Value::as_u64) else {
+                    continue;
+                };
+                if !given {
+                    continue;
+                }
+                if caged[r][c] {
+                    return Err(format!(
+                        "given digit at r{}c{} inside a cage is not supported",
+                        r + 1,
+                        c + 1
+                    ));
+                }
+                caged[r][c] = true;
+                cages.push(Cage {
+                    sum: value as u32,
+                    cells: vec![Cell::new(r, c)],
+                    lines: None,
+                });
+            }
+        }
+        if cages.is_empty() {
+            return Err("no killer cages found".to_string());
+        }
+        SumdokuBoard::from_cages(shape, cages)
+    }
+
+    /// Writes the board in the f-puzzles format, which SudokuPad also opens.
+    pub fn to_json(&self) -> String {
+        let size = self.shape.size;
+        let grid = vec![vec![json!({}); size]; size];
+        let cages: Vec<Value> = self
+            .cages
+            .iter()
+            .map(|cage| {
+                let cells: Vec<String> = cage
+                    .cells
+                    .iter()
+                    .map(|c| format!("R{}C{}", c.row + 1, c.col + 1))
+                    .collect();
+                json!({ "cells": cells, "value": cage.sum.to_string() })
+            })
+            .collect();
+        json!({ "size": size, "grid": grid, "killercage": cages }).to_string()
+    }
+}
+
+/// Reads one cage entry. Cages without a sum are skipped.
+fn parse_cage(entry: &Value, size: usize) -> Result<Option<Cage>, String> {
+    let sum = match entry.get("value") {
+        Some(Value::String(s)) if s.trim().is_empty() => return Ok(None),
+        Some(Value::String(s)) => s
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid sum '{}'", s))?,
+        Some(Value::Number(n)) => n.as_u64().ok_or(format!("invalid sum {}", n))? as u32,
+        None | Some(Value::Null) => return Ok(None),
+        Some(other) => return Err(format!("invalid sum {}", other)),
+    };
+    let cells = match entry.get("cells") {
+        Some(Value::Array(cells)) => cells
+            .iter()
+            .map(|c| parse_cell(c, size))
+            .collect::<Result<_, _>>()?,
+        Some(Value::String(cells)) => cells
+            .split(',')
+            .map(|c| parse_reference(c.trim(), size))
+            .collect::<Result<_, _>>()?,
+        _ => return Err("missing cells".to_string()),
+    };
+    let mut cage = Cage {
+        sum,
+        cells,
+        lines: None,
+    };
+    if cage.cells.is_empty() {
+        return Err("cage has no cells".to_string());
+    }
+    cage.cells.sort_by_key(|c| c.row * 100 + c.col);
+    Ok(Some(cage))
+}
+
+fn parse_cell(cell: &Value, size: usize) -> Result<Cell, String> {
+    match cell {
+        Value::String(reference) => parse_reference(reference, size),
+        Value::Array(pair) => {
+            let index = |i: usize| pair.get(i).and_then(Value::as_u64).map(|v| v as usize);
+            match (index(0), index(1)) {
+                (Some(row), Some(col)) if row < size && col < size => Ok(Cell::new(row, col)),
+                _ => Err(format!("invalid cell {}", cell)),
+            }
+        }
+        _ => Err(format!("invalid cell {}", cell)),
+    }
+}
+
+/// Parses a cell written as `R1C1`, case insensitive.
+fn parse_reference(reference: &str, size: usize) -> Result<Cell, String> {
+    let invalid = || format!("invalid cell '{}'", reference);
+    let upper = reference.to_ascii_uppercase();
+    let (row, col) = upper
+        .strip_prefix('R')
+        .and_then(|rest| rest.split_once('C'))
+        .ok_or_else(invalid)?;
+    let row: usize = row.parse().map_err(|_| invalid())?;
+    let col: usize = col.parse().map_err(|_| invalid())?;
+    if !(1..=size).contains(&row) || !(1..=size).contains(&col) {
+        return Err(invalid());
+    }
+    Ok(Cell::new(row - 1, col - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_every_size() {
+        for shape in Shape::SUPPORTED {
+            let board = SumdokuBoard::from_seed(3, shape, 6);
+            let parsed = SumdokuBoard::from_json(&board.to_json()).unwrap();
+            assert_eq!(parsed.shape, shape);
+            assert_eq!(parsed.solution, board.solution);
+            assert_eq!(parsed.to_text(), board.to_text());
+        }
+    }
+
+    #[test]
+    fn reads_sudokupad_cages_and_givens() {
+        let board = SumdokuBoard::from_seed(5, Shape::new(2, 2), 3);
+        let mut cages: Vec<Value> = board
+            .cages
+            .iter()
+            .map(|cage| {
+                let cells: Vec<[usize; 2]> = cage.cells.iter().map(|c| [c.row, c.col]).collect();
+                json!({ "cells": cells, "value": cage.sum })
+            })
+            .collect();
+        // a single cell cage as a given digit instead
+        let single = board.cages.iter().position(|cage| cage.cells.len() == 1);
+        let single = single.unwrap();
+        let cell = board.cages[single].cells[0];
+        let mut grid = vec![vec![json!({}); 4]; 4];
+        grid[cell.row][cell.col] = json!({ "value": board.cages[single].sum });
+        cages.remove(single);
+        cages.push(json!({ "cells": ["R1C1"], "style": "arrow" }));
+        let text = json!({ "size": 4, "cells": grid, "cages": cages }).to_string();
+        let parsed = SumdokuBoard::from_json(&text).unwrap();
+        assert_eq!(parsed.solution, board.solution);
+    }
+
+    #[test]
+    fn reports_bad_cages() {
+        let error = |text: &str| SumdokuBoard::from_json(text).err().unwrap();
+        assert_eq!(
+            error(r#"{ "size": 5, "killercage": [] }"#),
+            "unsupported grid size 5"
+        );
+        assert_eq!(
+            error(r#"{ "size": 4, "killercage": [{ "cells": ["R1C5"], "value": "3" }] }"#),
+            "killercage 0: invalid cell 'R1C5'"
+        );
+        assert_eq!(
+            error(r#"{ "size": 4, "cages": [{ "cells": [[0, 0]], "value": "x" }] }"#),
+            "cages 0: invalid sum 'x'"
+        );
+        assert_eq!(
+            error(
+                r#"{ "size": 4, "killercage": [
+                    { "cells": ["R1C1", "R1C2"], "value": "3" },
+                    { "cells": ["r1c2"], "value": "2" }
+                ] }"#
+            ),
+            "r1c2 is in more than one cage"
+        );
+        assert_eq!(error(r#"{ "size": 4 }"#), "no killer cages found");
+    }
+}
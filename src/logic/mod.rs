@@ -2,5 +2,26 @@ pub mod board;
 
 pub use board::*;
 
+pub mod shape;
+pub use shape::*;
+
 pub mod figure;
-pub use figure::*;
\ No newline at end of file
+pub use figure::*;
+
+pub mod combination;
+pub use combination::*;
+
+pub mod solver;
+pub use solver::*;
+
+pub mod techniques;
+pub use techniques::*;
+
+pub mod rating;
+pub use rating::*;
+
+pub mod text;
+
+pub mod fpuzzles;
+
+pub mod print;
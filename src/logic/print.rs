@@ -0,0 +1,336 @@
+//! Printable puzzles as SVG or PDF, laid out on an A4 page: the grid, cages
+//! drawn as dashed outlines set inside their cells, and each cage sum in the
+//! top left cell of its cage. A solution page, with every digit filled in,
+//! can follow the puzzle.
+//!
+//! Lengths are in points. The PDF is written by hand with the standard
+//! Helvetica font, so it needs no font files.
+
+use super::{Figure, Line, SumdokuBoard};
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const GRID_WIDTH: usize = 480;
+const GRID_TOP: f32 = 110.0;
+const THIN_LINE: f32 = 0.5;
+const THICK_LINE: f32 = 2.0;
+const CAGE_LINE: f32 = 0.75;
+/// Helvetica digits are all this wide, as a share of the font size.
+const DIGIT_WIDTH: f32 = 0.556;
+
+/// Something drawn on a page, with y growing down the page.
+enum Mark {
+    Line {
+        from: (f32, f32),
+        to: (f32, f32),
+        width: f32,
+        dashed: bool,
+    },
+    /// A white box, to keep cage outlines clear of the sums.
+    Blank {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+    /// Text starting at `x`, or centred on it, with its baseline at `y`.
+    /// Only numbers are centred.
+    Text {
+        x: f32,
+        y: f32,
+        size: f32,
+        text: String,
+        centered: bool,
+    },
+}
+
+impl SumdokuBoard {
+    /// The puzzle as an SVG image, or its solution when `solution` is set.
+    pub fn to_svg(&self, solution: bool) -> String {
+        svg_page(&self.page(solution))
+    }
+
+    /// The puzzle as a PDF document, with a solution page after it when
+    /// `solution` is set.
+    pub fn to_pdf(&self, solution: bool) -> Vec<u8> {
+        let mut pages = vec![self.page(false)];
+        if solution {
+            pages.push(self.page(true));
+        }
+        pdf_document(&pages)
+    }
+
+    fn page(&self, solution: bool) -> Vec<Mark> {
+        let size = self.shape.size;
+        let cell = GRID_WIDTH / size;
+        let grid = (cell * size) as f32;
+        let left = (PAGE_WIDTH - grid) / 2.0;
+        let top = GRID_TOP;
+        let mut marks = Vec::new();
+
+        let title = format!(
+            "Killer sudoku {}x{}, {}{}",
+            size,
+            size,
+            self.difficulty,
+            if solution { ", solution" } else { "" }
+        );
+        marks.push(Mark::Text {
+            x: left,
+            y: top - 30.0,
+            size: 18.0,
+            text: title,
+            centered: false,
+        });
+
+        for i in 0..=size {
+            let at = (i * cell) as f32;
+            let vertical = if i % self.shape.box_cols == 0 {
+                THICK_LINE
+            } else {
+                THIN_LINE
+            };
+            let horizontal = if i % self.shape.box_rows == 0 {
+                THICK_LINE
+            } else {
+                THIN_LINE
+            };
+            marks.push(Mark::Line {
+                from: (left + at, top),
+                to: (left + at, top + grid),
+                width: vertical,
+                dashed: false,
+            });
+            marks.push(Mark::Line {
+                from: (left, top + at),
+                to: (left + grid, top + at),
+                width: horizontal,
+                dashed: false,
+            });
+        }
+
+        let padding = (cell / 10).max(2) as i32;
+        let sum_size = (cell as f32 / 5.0).clamp(7.0, 11.0);
+        for cage in &self.cages {
+            let lines = Line::outline(cell, &cage.cells);
+            for edge in Figure::from_lines(padding, &lines).edges {
+                marks.push(Mark::Line {
+                    from: (left + edge.start.x as f32, top + edge.start.y as f32),
+                    to: (left + edge.end.x as f32, top + edge.end.y as f32),
+                    width: CAGE_LINE,
+                    dashed: true,
+                });
+            }
+            let Some(first) = cage.cells.iter().min_by_key(|c| (c.row, c.col)) else {
+                continue;
+            };
+            let text = cage.sum.to_string();
+            let x = left + (first.col * cell) as f32 + padding as f32;
+            let y = top + (first.row * cell) as f32 + padding as f32;
+            let width = text.len() as f32 * DIGIT_WIDTH * sum_size + 2.0;
+            marks.push(Mark::Blank {
+                x,
+                y,
+                width,
+                height: sum_size + 1.0,
+            });
+            marks.push(Mark::Text {
+                x: x + 1.0,
+                y: y + sum_size * 0.85,
+                size: sum_size,
+                text,
+                centered: false,
+            });
+        }
+
+        if solution {
+            let digit_size = cell as f32 * 0.55;
+            for (r, row) in self.solution.iter().enumerate() {
+                for (c, digit) in row.iter().enumerate() {
+                    marks.push(Mark::Text {
+                        x: left + (c * cell) as f32 + cell as f32 / 2.0,
+                        y: top + (r * cell) as f32 + (cell as f32 + digit_size * 0.7) / 2.0,
+                        size: digit_size,
+                        text: digit.to_string(),
+                        centered: true,
+                    });
+                }
+            }
+        }
+        marks
+    }
+}
+
+fn svg_page(marks: &[Mark]) -> String {
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}pt\" height=\"{h}pt\" \
+         viewBox=\"0 0 {w} {h}\">\n<rect width=\"{w}\" height=\"{h}\" fill=\"white\"/>\n",
+        w = PAGE_WIDTH,
+        h = PAGE_HEIGHT
+    );
+    for mark in marks {
+        let element = match mark {
+            Mark::Line {
+                from,
+                to,
+                width,
+                dashed,
+            } => format!(
+                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\" \
+                 stroke-width=\"{}\" stroke-linecap=\"square\"{}/>",
+                from.0,
+                from.1,
+                to.0,
+                to.1,
+                width,
+                if *dashed {
+                    " stroke-dasharray=\"3 2\""
+                } else {
+                    ""
+                }
+            ),
+            Mark::Blank {
+                x,
+                y,
+                width,
+                height,
+            } => format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"white\"/>",
+                x, y, width, height
+            ),
+            Mark::Text {
+                x,
+                y,
+                size,
+                text,
+                centered,
+            } => format!(
+                "<text x=\"{}\" y=\"{}\" font-family=\"Helvetica, Arial, sans-serif\" \
+                 font-size=\"{}\"{}>{}</text>",
+                x,
+                y,
+                size,
+                if *centered {
+                    " text-anchor=\"middle\""
+                } else {
+                    ""
+                },
+                text
+            ),
+        };
+        svg.push_str(&element);
+        svg.push('\n');
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// The drawing operators for one page. PDF measures y up from the bottom.
+fn pdf_content(marks: &[Mark]) -> String {
+    let mut content = String::from("2 J\n");
+    for mark in marks {
+        let op = match mark {
+            Mark::Line {
+                from,
+                to,
+                width,
+                dashed,
+            } => format!(
+                "{} w {} d {:.2} {:.2} m {:.2} {:.2} l S\n",
+                width,
+                if *dashed { "[3 2] 0" } else { "[] 0" },
+                from.0,
+                PAGE_HEIGHT - from.1,
+                to.0,
+                PAGE_HEIGHT - to.1
+            ),
+            Mark::Blank {
+                x,
+                y,
+                width,
+                height,
+            } => format!(
+                "1 g {:.2} {:.2} {:.2} {:.2} re f 0 g\n",
+                x,
+                PAGE_HEIGHT - y - height,
+                width,
+                height
+            ),
+            Mark::Text {
+                x,
+                y,
+                size,
+                text,
+                centered,
+            } => {
+                // only digits are centred, so the width of one is enough
+                let width = text.len() as f32 * DIGIT_WIDTH * size;
+                let x = if *centered { x - width / 2.0 } else { *x };
+                format!(
+                    "BT /F1 {} Tf {:.2} {:.2} Td ({}) Tj ET\n",
+                    size,
+                    x,
+                    PAGE_HEIGHT - y,
+                    text.replace('\\', "\\\\")
+                        .replace('(', "\\(")
+                        .replace(')', "\\)")
+                )
+            }
+        };
+        content.push_str(&op);
+    }
+    content
+}
+
+fn pdf_document(pages: &[Vec<Mark>]) -> Vec<u8> {
+    // objects 1 to 3 are the catalog, the page tree and the font; each page
+    // then takes two, itself and its content
+    let page_ids: Vec<usize> = (0..pages.len()).map(|i| 4 + 2 * i).collect();
+    let kids: Vec<String> = page_ids.iter().map(|id| format!("{} 0 R", id)).collect();
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    ];
+    for (page, id) in pages.iter().zip(&page_ids) {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+             /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH,
+            PAGE_HEIGHT,
+            id + 1
+        ));
+        let content = pdf_content(page);
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ));
+    }
+
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.push_str(&format!("{} 0 obj\n{}\nendobj\n", i + 1, object));
+    }
+    let xref = pdf.len();
+    pdf.push_str(&format!(
+        "xref\n0 {}\n0000000000 65535 f \n",
+        objects.len() + 1
+    ));
+    for offset in offsets {
+        pdf.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    pdf.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    ));
+    pdf.into_bytes()
+}
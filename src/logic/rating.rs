@@ -0,0 +1,108 @@
+use std::{fmt::Display, str::FromStr};
+
+use super::{HumanSolver, SumdokuBoard};
+
+/// How hard a puzzle is, judged by the hardest technique it takes to solve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    /// Needs more than the techniques the human solver knows.
+    Fiendish,
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+            Difficulty::Fiendish => "Fiendish",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    /// Reads a difficulty by name, in any case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "easy" => Ok(Difficulty::Easy),
+            "medium" => Ok(Difficulty::Medium),
+            "hard" => Ok(Difficulty::Hard),
+            "fiendish" => Ok(Difficulty::Fiendish),
+            _ => Err(format!("unknown difficulty {}", s)),
+        }
+    }
+}
+
+/// Solves the board step by step, always taking the easiest deduction, and
+/// grades it by the hardest technique that was needed.
+pub fn rate(board: &SumdokuBoard) -> Difficulty {
+    let mut solver = HumanSolver::new(board);
+    let mut difficulty = Difficulty::Easy;
+    while !solver.is_solved() {
+        let Some(step) = solver.next_step() else {
+            return Difficulty::Fiendish;
+        };
+        difficulty = difficulty.max(step.technique.difficulty());
+        solver.apply(&step);
+    }
+    difficulty
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::{Shape, Technique};
+
+    /// The techniques `rate` goes through on the board, in first use order.
+    fn techniques(board: &SumdokuBoard) -> Vec<Technique> {
+        let mut solver = HumanSolver::new(board);
+        let mut used = Vec::new();
+        while let Some(step) = solver.next_step() {
+            if !used.contains(&step.technique) {
+                used.push(step.technique);
+            }
+            solver.apply(&step);
+        }
+        used
+    }
+
+    #[test]
+    fn generated_boards_carry_their_rating() {
+        for seed in 0..8 {
+            let board = SumdokuBoard::from_seed(seed, Shape::CLASSIC, 6);
+            assert_eq!(board.difficulty, rate(&board));
+            assert_eq!(rate(&board), rate(&board));
+            let again = SumdokuBoard::from_seed(seed, Shape::CLASSIC, 6);
+            assert_eq!(again.difficulty, board.difficulty);
+        }
+    }
+
+    #[test]
+    fn rates_by_the_hardest_technique_needed() {
+        let easy = SumdokuBoard::from_seed(1, Shape::CLASSIC, 6);
+        assert_eq!(rate(&easy), Difficulty::Easy);
+        assert!(techniques(&easy)
+            .iter()
+            .all(|technique| technique.difficulty() == Difficulty::Easy));
+        let innies = SumdokuBoard::from_seed(0, Shape::CLASSIC, 6);
+        assert_eq!(rate(&innies), Difficulty::Medium);
+        assert!(techniques(&innies).contains(&Technique::Rule45));
+    }
+
+    #[test]
+    fn with_difficulty_gives_the_grade_asked_for() {
+        for difficulty in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+            let board = SumdokuBoard::from_seed_with_difficulty(1, Shape::CLASSIC, 6, difficulty);
+            assert_eq!(board.difficulty, difficulty);
+            assert_eq!(rate(&board), difficulty);
+        }
+        let board = SumdokuBoard::with_difficulty(Shape::CLASSIC, 6, Difficulty::Easy);
+        assert_eq!(board.difficulty, Difficulty::Easy);
+    }
+}
@@ -0,0 +1,68 @@
+use std::fmt::Display;
+
+/// Grid dimensions: a `size` x `size` grid split into boxes of `box_rows`
+/// rows by `box_cols` columns, holding the digits 1 to `size`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shape {
+    pub size: usize,
+    pub box_rows: usize,
+    pub box_cols: usize,
+}
+
+impl Shape {
+    pub const CLASSIC: Shape = Shape::new(3, 3);
+
+    /// Sizes with the usual box shape for each.
+    pub const SUPPORTED: [Shape; 5] = [
+        Shape::new(2, 2),
+        Shape::new(2, 3),
+        Shape::new(2, 4),
+        Shape::CLASSIC,
+        Shape::new(3, 4),
+    ];
+
+    pub const fn new(box_rows: usize, box_cols: usize) -> Self {
+        Shape {
+            size: box_rows * box_cols,
+            box_rows,
+            box_cols,
+        }
+    }
+
+    pub fn for_size(size: usize) -> Option<Shape> {
+        Shape::SUPPORTED.into_iter().find(|s| s.size == size)
+    }
+
+    pub fn cell_count(&self) -> usize {
+        self.size * self.size
+    }
+
+    /// What every row, column and box adds up to: 45 on a classic grid.
+    pub fn house_sum(&self) -> u32 {
+        (1..=self.size as u32).sum()
+    }
+
+    /// Index of the box holding the cell, counting across then down.
+    pub fn box_index(&self, row: usize, col: usize) -> usize {
+        (row / self.box_rows) * self.boxes_across() + col / self.box_cols
+    }
+
+    /// Top left cell of the box with the given index.
+    pub fn box_origin(&self, index: usize) -> (usize, usize) {
+        let across = self.boxes_across();
+        (
+            (index / across) * self.box_rows,
+            (index % across) * self.box_cols,
+        )
+    }
+
+    fn boxes_across(&self) -> usize {
+        self.size / self.box_cols
+    }
+}
+
+impl Display for Shape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}x{}", self.size, self.box_rows, self.box_cols)
+    }
+}
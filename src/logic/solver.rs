@@ -0,0 +1,392 @@
+use super::{combination_mask, combination_sum, digits, SumdokuBoard};
+
+/// Innies longer than this are left out. Every innie of a 9x9 grid fits;
+/// on a 12x12 grid the longest ones are skipped, as they can take thousands
+/// of digit sets and narrow little.
+const MAX_INNIE_CELLS: usize = 8;
+
+/// Result of solving a board from its cages alone.
+#[derive(Debug, PartialEq)]
+pub enum Solution {
+    None,
+    Unique(Vec<Vec<u32>>),
+    /// Two different grids that both satisfy every cage.
+    Multiple(Vec<Vec<u32>>, Vec<Vec<u32>>),
+}
+
+impl Solution {
+    pub fn is_unique(&self) -> bool {
+        matches!(self, Solution::Unique(_))
+    }
+}
+
+/// Solves a board using only its cages, ignoring `board.solution`.
+///
+/// Candidates are narrowed by row, column and box peers, hidden singles and
+/// the digit combinations that can still make up each cage sum. When
+/// propagation stalls the solver branches on the cell with the fewest
+/// candidates and stops as soon as a second solution turns up.
+pub fn solve(board: &SumdokuBoard) -> Solution {
+    solve_within(board, usize::MAX).expect("an unbounded search always finishes")
+}
+
+/// Like `solve`, but gives up and returns `None` once it has guessed
+/// `guesses` times, for callers that would rather try another puzzle than
+/// wait on a hard one.
+pub fn solve_within(board: &SumdokuBoard, guesses: usize) -> Option<Solution> {
+    let solver = Solver::new(board);
+    let mut solutions = Vec::new();
+    let mut candidates = vec![solver.all_digits(); solver.size * solver.size];
+    let mut narrowed = vec![Vec::new(); solver.cages.len() + solver.innies.len()];
+    let mut budget = guesses;
+    if solver.propagate(&mut candidates, &mut narrowed)
+        && !solver.search(candidates, narrowed, &mut solutions, &mut budget)
+    {
+        return None;
+    }
+    let mut solutions = solutions.into_iter().map(|c| solver.to_grid(&c));
+    Some(match (solutions.next(), solutions.next()) {
+        (None, _) => Solution::None,
+        (Some(first), None) => Solution::Unique(first),
+        (Some(first), Some(second)) => Solution::Multiple(first, second),
+    })
+}
+
+pub(super) struct SolverCage {
+    pub sum: u32,
+    pub cells: Vec<usize>,
+    /// The house an innie was taken from. Real cages have none.
+    pub house: Option<usize>,
+}
+
+pub(super) struct Solver {
+    pub size: usize,
+    /// Rows, then columns, then boxes, as lists of cell indices.
+    pub houses: Vec<Vec<usize>>,
+    /// Cells sharing a house or a cage with each cell.
+    pub peers: Vec<Vec<usize>>,
+    pub cages: Vec<SolverCage>,
+    /// Cells of a house left over once the cages lying fully inside it are
+    /// taken out. They must hold the rest of the house total (the 45 rule).
+    pub innies: Vec<SolverCage>,
+}
+
+impl Solver {
+    pub fn new(board: &SumdokuBoard) -> Self {
+        let shape = board.shape;
+        let size = shape.size;
+        let mut houses = Vec::new();
+        for r in 0..size {
+            houses.push((0..size).map(|c| r * size + c).collect());
+        }
+        for c in 0..size {
+            houses.push((0..size).map(|r| r * size + c).collect());
+        }
+        for b in 0..size {
+            let (br, bc) = shape.box_origin(b);
+            let mut house = Vec::new();
+            for r in br..br + shape.box_rows {
+                for c in bc..bc + shape.box_cols {
+                    house.push(r * size + c);
+                }
+            }
+            houses.push(house);
+        }
+        let cages: Vec<SolverCage> = board
+            .cages
+            .iter()
+            .map(|cage| SolverCage {
+                sum: cage.sum,
+                cells: cage.cells.iter().map(|c| c.row * size + c.col).collect(),
+                house: None,
+            })
+            .collect();
+        let mut innies = Vec::new();
+        let house_sum = shape.house_sum();
+        for (index, house) in houses.iter().enumerate() {
+            let inside: Vec<&SolverCage> = cages
+                .iter()
+                .filter(|cage| cage.cells.iter().all(|c| house.contains(c)))
+                .collect();
+            let rest: Vec<usize> = house
+                .iter()
+                .copied()
+                .filter(|c| !inside.iter().any(|cage| cage.cells.contains(c)))
+                .collect();
+            // the digit sets a long innie can take run into the thousands, and it narrows little
+            if !inside.is_empty() && !rest.is_empty() && rest.len() <= MAX_INNIE_CELLS {
+                innies.push(SolverCage {
+                    sum: house_sum.saturating_sub(inside.iter().map(|cage| cage.sum).sum()),
+                    cells: rest,
+                    house: Some(index),
+                });
+            }
+        }
+        let mut peers = vec![Vec::new(); size * size];
+        let groups = houses.iter().chain(cages.iter().map(|c| &c.cells));
+        for group in groups {
+            for &cell in group {
+                for &other in group {
+                    if other != cell && !peers[cell].contains(&other) {
+                        peers[cell].push(other);
+                    }
+                }
+            }
+        }
+        Solver {
+            size,
+            houses,
+            peers,
+            cages,
+            innies,
+        }
+    }
+
+    pub fn all_digits(&self) -> u16 {
+        combination_mask(1..=self.size as u32)
+    }
+
+    /// Narrows candidates until nothing changes. Returns false on a contradiction.
+    ///
+    /// `narrowed` holds what the last pass left in each cage and innie, so
+    /// that cages nothing has touched since are skipped.
+    fn propagate(&self, candidates: &mut [u16], narrowed: &mut [Vec<u16>]) -> bool {
+        let mut settled = vec![false; candidates.len()];
+        loop {
+            let mut changed = false;
+
+            for cell in 0..candidates.len() {
+                if candidates[cell] == 0 {
+                    return false;
+                }
+                if settled[cell] || candidates[cell].count_ones() != 1 {
+                    continue;
+                }
+                settled[cell] = true;
+                for &peer in &self.peers[cell] {
+                    if candidates[peer] & candidates[cell] != 0 {
+                        candidates[peer] &= !candidates[cell];
+                        if candidates[peer] == 0 {
+                            return false;
+                        }
+                        changed = true;
+                    }
+                }
+            }
+
+            for house in &self.houses {
+                for d in 1..=self.size as u32 {
+                    let mask = 1u16 << d;
+                    let mut places = house.iter().filter(|&&c| candidates[c] & mask != 0);
+                    match (places.next(), places.next()) {
+                        (None, _) => return false,
+                        (Some(&cell), None) if candidates[cell] != mask => {
+                            candidates[cell] = mask;
+                            changed = true;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            for (cage, last) in self
+                .cages
+                .iter()
+                .chain(&self.innies)
+                .zip(narrowed.iter_mut())
+            {
+                let cage_candidates: Vec<u16> = cage.cells.iter().map(|&c| candidates[c]).collect();
+                if cage_candidates == *last {
+                    continue;
+                }
+                let possible = cage_options(cage.sum, &cage_candidates, self.size as u32);
+                for (i, &cell) in cage.cells.iter().enumerate() {
+                    if possible[i] == 0 {
+                        return false;
+                    }
+                    if possible[i] != candidates[cell] {
+                        candidates[cell] = possible[i];
+                        changed = true;
+                    }
+                }
+                *last = possible;
+            }
+
+            if !changed {
+                return true;
+            }
+        }
+    }
+
+    /// Returns false if it ran out of `budget` guesses before finishing.
+    fn search(
+        &self,
+        candidates: Vec<u16>,
+        narrowed: Vec<Vec<u16>>,
+        solutions: &mut Vec<Vec<u16>>,
+        budget: &mut usize,
+    ) -> bool {
+        if solutions.len() > 1 {
+            return true;
+        }
+        let open = (0..candidates.len())
+            .filter(|&c| candidates[c].count_ones() > 1)
+            .min_by_key(|&c| candidates[c].count_ones());
+        let Some(cell) = open else {
+            solutions.push(candidates);
+            return true;
+        };
+        for d in digits(candidates[cell]) {
+            if *budget == 0 {
+                return false;
+            }
+            *budget -= 1;
+            let mut next = candidates.clone();
+            let mut next_narrowed = narrowed.clone();
+            next[cell] = 1 << d;
+            if self.propagate(&mut next, &mut next_narrowed) {
+                if !self.search(next, next_narrowed, solutions, budget) {
+                    return false;
+                }
+                if solutions.len() > 1 {
+                    return true;
+                }
+            }
+        }
+        true
+    }
+
+    fn to_grid(&self, candidates: &[u16]) -> Vec<Vec<u32>> {
+        candidates
+            .chunks(self.size)
+            .map(|row| row.iter().map(|c| c.trailing_zeros()).collect())
+            .collect()
+    }
+}
+
+/// For every cell of a cage returns the candidates that take part in at least
+/// one assignment of distinct digits adding up to `sum`.
+pub(super) fn cage_options(sum: u32, candidates: &[u16], max_digit: u32) -> Vec<u16> {
+    let n = candidates.len();
+    let states = 1usize << (max_digit + 1);
+    // reached[i]: digit sets cells before i can hold that may still reach the sum
+    let mut reached = vec![vec![0u16]];
+    let mut seen = vec![false; states];
+    for (i, &cell) in candidates.iter().enumerate() {
+        // the cells after this one need at least the smallest and at most
+        // the largest digits
+        let left = (n - i - 1) as u32;
+        let least = left * (left + 1) / 2;
+        let most = left * (2 * max_digit + 1).saturating_sub(left) / 2;
+        let mut next = Vec::new();
+        for &m in &reached[i] {
+            for d in digits(cell & !m) {
+                let mask = m | 1 << d;
+                let total = combination_sum(mask);
+                if !seen[mask as usize] && total + least <= sum && sum <= total + most {
+                    seen[mask as usize] = true;
+                    next.push(mask);
+                }
+            }
+        }
+        reached.push(next);
+    }
+    // seen now marks every reached set. Sets that cannot finish the sum are
+    // cleared layer by layer from the back; a layer's sets all have the same
+    // number of digits, so layers never mix.
+    for &m in &reached[n] {
+        seen[m as usize] = combination_sum(m) == sum;
+    }
+    let mut possible = vec![0u16; n];
+    for i in (0..n).rev() {
+        for &m in &reached[i] {
+            let mut finishes = false;
+            for d in digits(candidates[i] & !m) {
+                if seen[(m | 1 << d) as usize] {
+                    finishes = true;
+                    possible[i] |= 1 << d;
+                }
+            }
+            seen[m as usize] = finishes;
+        }
+    }
+    possible
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::{Cage, Cell, Difficulty, Shape};
+
+    fn board(shape: Shape, cages: &[(u32, &[(usize, usize)])]) -> SumdokuBoard {
+        let cages = cages
+            .iter()
+            .map(|&(sum, cells)| Cage {
+                sum,
+                cells: cells.iter().map(|&(r, c)| Cell::new(r, c)).collect(),
+                lines: None,
+            })
+            .collect();
+        SumdokuBoard {
+            shape,
+            solution: Vec::new(),
+            cages,
+            difficulty: Difficulty::Easy,
+            seed: None,
+        }
+    }
+
+    #[test]
+    fn solves_generated_boards_to_their_solution() {
+        for shape in Shape::SUPPORTED {
+            let board = SumdokuBoard::from_seed(1, shape, 6);
+            assert_eq!(solve(&board), Solution::Unique(board.solution.clone()));
+        }
+    }
+
+    #[test]
+    fn finds_two_solutions_of_an_open_grid() {
+        let board = board(Shape::new(2, 2), &[(1, &[(0, 0)])]);
+        let Solution::Multiple(first, second) = solve(&board) else {
+            panic!("expected more than one solution");
+        };
+        assert_ne!(first, second);
+        assert_eq!(first[0][0], 1);
+        assert_eq!(second[0][0], 1);
+    }
+
+    #[test]
+    fn finds_no_solution_for_impossible_sums() {
+        let too_large = board(Shape::new(2, 2), &[(5, &[(0, 0)])]);
+        assert_eq!(solve(&too_large), Solution::None);
+        let repeated = board(Shape::new(2, 2), &[(1, &[(0, 0)]), (1, &[(0, 1)])]);
+        assert_eq!(solve(&repeated), Solution::None);
+    }
+
+    #[test]
+    fn cage_larger_than_the_digits_has_no_solution() {
+        let cells: Vec<(usize, usize)> = (0..16).map(|i| (i / 4, i % 4)).collect();
+        let board = board(Shape::new(2, 2), &[(40, &cells)]);
+        assert_eq!(solve(&board), Solution::None);
+        assert_eq!(cage_options(40, &[0b11110; 16], 4), vec![0; 16]);
+    }
+
+    #[test]
+    fn gives_up_once_out_of_guesses() {
+        let board = board(Shape::new(2, 2), &[(1, &[(0, 0)])]);
+        assert_eq!(solve_within(&board, 0), None);
+        assert!(solve_within(&board, 100).is_some());
+    }
+
+    #[test]
+    fn cage_options_keep_digits_of_some_combination() {
+        let all = combination_mask(1..=9);
+        // 3 in two cells is only 1 + 2
+        assert_eq!(cage_options(3, &[all, all], 9), vec![0b110, 0b110]);
+        // 17 in two cells is only 8 + 9
+        assert_eq!(cage_options(17, &[all, all], 9), vec![0b11_0000_0000; 2]);
+        // with 1 ruled out of the second cell, 4 is 1 + 3 only
+        let no_one = all & !0b10;
+        assert_eq!(cage_options(4, &[all, no_one], 9), vec![0b10, 0b1000]);
+    }
+}
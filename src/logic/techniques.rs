@@ -0,0 +1,549 @@
+use super::combination::fitting_combinations;
+use super::solver::{cage_options, Solver, SolverCage};
+use super::{digits, Cell, Difficulty, SumdokuBoard};
+
+/// Deductions a human solver uses, ordered from easiest to hardest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Technique {
+    NakedSingle,
+    HiddenSingle,
+    CageCombination,
+    /// Innies and outies: a house always adds up to 45.
+    Rule45,
+    /// A digit confined to one house inside another.
+    Pointing,
+    /// A digit every remaining cage combination needs, confined to one house.
+    CageInteraction,
+    NakedSubset,
+    HiddenSubset,
+}
+
+impl Technique {
+    pub fn difficulty(self) -> Difficulty {
+        match self {
+            Technique::NakedSingle | Technique::HiddenSingle | Technique::CageCombination => {
+                Difficulty::Easy
+            }
+            Technique::Rule45 | Technique::Pointing => Difficulty::Medium,
+            Technique::CageInteraction | Technique::NakedSubset | Technique::HiddenSubset => {
+                Difficulty::Hard
+            }
+        }
+    }
+}
+
+/// One deduction: digits to place and candidates to rule out.
+#[derive(Debug)]
+pub struct Step {
+    pub technique: Technique,
+    pub placements: Vec<(Cell, u32)>,
+    /// Cells with a bit mask of the digits removed from their candidates.
+    pub eliminations: Vec<(Cell, u16)>,
+    /// Cells the deduction rests on, such as the cage or house it looks at.
+    pub cells: Vec<Cell>,
+    /// Why the step holds and what it changes, e.g. "cage 23/3 at r1c1 must
+    /// be {6,8,9}, so r1c2 can't be 5".
+    pub explanation: String,
+}
+
+/// Works through a board one human-style deduction at a time, keeping
+/// candidates as bit masks where bit `d` stands for digit `d`.
+pub struct HumanSolver {
+    solver: Solver,
+    values: Vec<u32>,
+    candidates: Vec<u16>,
+}
+
+impl HumanSolver {
+    pub fn new(board: &SumdokuBoard) -> Self {
+        let solver = Solver::new(board);
+        let cells = solver.size * solver.size;
+        let candidates = vec![solver.all_digits(); cells];
+        HumanSolver {
+            solver,
+            values: vec![0; cells],
+            candidates,
+        }
+    }
+
+    /// Starts from a game in progress: `values` holds the digits entered so
+    /// far (0 for empty) and `excluded` digits already ruled out per cell.
+    /// The entered digits are taken to be right.
+    pub fn with_progress(board: &SumdokuBoard, values: &[Vec<u32>], excluded: &[Vec<u16>]) -> Self {
+        let mut solver = HumanSolver::new(board);
+        for (r, row) in values.iter().enumerate() {
+            for (c, &value) in row.iter().enumerate() {
+                if value != 0 {
+                    solver.place(solver.index(&Cell::new(r, c)), value);
+                }
+            }
+        }
+        for (r, row) in excluded.iter().enumerate() {
+            for (c, &mask) in row.iter().enumerate() {
+                let index = solver.index(&Cell::new(r, c));
+                if solver.is_open(index) {
+                    solver.candidates[index] &= !mask;
+                }
+            }
+        }
+        solver
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.values.iter().all(|&v| v != 0)
+    }
+
+    /// Finds the easiest deduction available, if any.
+    pub fn next_step(&self) -> Option<Step> {
+        self.naked_single()
+            .or_else(|| self.hidden_single())
+            .or_else(|| self.cage_combination())
+            .or_else(|| self.rule_45())
+            .or_else(|| self.pointing())
+            .or_else(|| self.cage_interaction())
+            .or_else(|| self.naked_subset())
+            .or_else(|| self.hidden_subset())
+    }
+
+    pub fn apply(&mut self, step: &Step) {
+        for (cell, mask) in &step.eliminations {
+            let index = self.index(cell);
+            self.candidates[index] &= !mask;
+        }
+        for (cell, value) in &step.placements {
+            self.place(self.index(cell), *value);
+        }
+    }
+
+    fn place(&mut self, index: usize, value: u32) {
+        self.values[index] = value;
+        self.candidates[index] = 1 << value;
+        for &peer in &self.solver.peers[index] {
+            if self.values[peer] == 0 {
+                self.candidates[peer] &= !(1 << value);
+            }
+        }
+    }
+
+    fn index(&self, cell: &Cell) -> usize {
+        cell.row * self.solver.size + cell.col
+    }
+
+    fn cell(&self, index: usize) -> Cell {
+        Cell::new(index / self.solver.size, index % self.solver.size)
+    }
+
+    fn is_open(&self, index: usize) -> bool {
+        self.values[index] == 0
+    }
+
+    fn cells(&self, indices: &[usize]) -> Vec<Cell> {
+        indices.iter().map(|&i| self.cell(i)).collect()
+    }
+
+    fn house_name(&self, index: usize) -> String {
+        let size = self.solver.size;
+        match index / size {
+            0 => format!("row {}", index % size + 1),
+            1 => format!("column {}", index % size + 1),
+            _ => format!("box {}", index % size + 1),
+        }
+    }
+
+    fn cage_name(&self, cage: &SolverCage) -> String {
+        let first = cage.cells.iter().min().copied().unwrap_or_default();
+        format!(
+            "cage {}/{} at {}",
+            cage.sum,
+            cage.cells.len(),
+            cell_name(&self.cell(first))
+        )
+    }
+
+    fn cell_list(&self, indices: &[usize]) -> String {
+        let names: Vec<String> = indices.iter().map(|&i| cell_name(&self.cell(i))).collect();
+        join(&names, "and")
+    }
+
+    /// Fills in the cells a step rests on and its explanation: the reason
+    /// followed by what the step changes.
+    fn explain(&self, mut step: Step, cells: &[usize], reason: String) -> Step {
+        let mut effects: Vec<String> = step
+            .placements
+            .iter()
+            .map(|(cell, value)| format!("{} is {}", cell_name(cell), value))
+            .collect();
+        // cells losing the same digits are named together
+        let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+        for (cell, mask) in &step.eliminations {
+            let left = self.candidates[self.index(cell)] & !mask;
+            let effect = if left.count_ones() < mask.count_ones() {
+                format!("can only be {}", digit_list(left, "or"))
+            } else {
+                format!("can't be {}", digit_list(*mask, "or"))
+            };
+            match groups.iter_mut().find(|(e, _)| *e == effect) {
+                Some((_, names)) => names.push(cell_name(cell)),
+                None => groups.push((effect, vec![cell_name(cell)])),
+            }
+        }
+        effects.extend(
+            groups
+                .into_iter()
+                .map(|(effect, names)| format!("{} {}", join(&names, "and"), effect)),
+        );
+        step.cells = self.cells(cells);
+        step.explanation = format!("{}, so {}", reason, join(&effects, "and"));
+        step
+    }
+
+    fn placement(&self, technique: Technique, index: usize, value: u32) -> Step {
+        Step {
+            technique,
+            placements: vec![(self.cell(index), value)],
+            eliminations: vec![],
+            cells: vec![],
+            explanation: String::new(),
+        }
+    }
+
+    /// Builds a step removing `mask` from the given cells, or nothing if none
+    /// of them still holds any of those digits.
+    fn elimination(&self, technique: Technique, cells: &[(usize, u16)]) -> Option<Step> {
+        let eliminations: Vec<(Cell, u16)> = cells
+            .iter()
+            .filter(|&&(index, mask)| self.is_open(index) && self.candidates[index] & mask != 0)
+            .map(|&(index, mask)| (self.cell(index), self.candidates[index] & mask))
+            .collect();
+        if eliminations.is_empty() {
+            return None;
+        }
+        Some(Step {
+            technique,
+            placements: vec![],
+            eliminations,
+            cells: vec![],
+            explanation: String::new(),
+        })
+    }
+
+    fn naked_single(&self) -> Option<Step> {
+        (0..self.values.len())
+            .find(|&i| self.is_open(i) && self.candidates[i].count_ones() == 1)
+            .map(|i| {
+                let value = self.candidates[i].trailing_zeros();
+                let reason = format!("{} has no other candidate", cell_name(&self.cell(i)));
+                self.explain(
+                    self.placement(Technique::NakedSingle, i, value),
+                    &[i],
+                    reason,
+                )
+            })
+    }
+
+    fn hidden_single(&self) -> Option<Step> {
+        for (h, house) in self.solver.houses.iter().enumerate() {
+            for d in 1..=self.solver.size as u32 {
+                if house.iter().any(|&c| self.values[c] == d) {
+                    continue;
+                }
+                let mut places = house
+                    .iter()
+                    .filter(|&&c| self.is_open(c) && self.candidates[c] & 1 << d != 0);
+                if let (Some(&cell), None) = (places.next(), places.next()) {
+                    let reason = format!("{} fits only one cell of {}", d, self.house_name(h));
+                    return Some(self.explain(
+                        self.placement(Technique::HiddenSingle, cell, d),
+                        house,
+                        reason,
+                    ));
+                }
+            }
+        }
+        None
+    }
+
+    fn restrict_to_sum(&self, technique: Technique, cage: &SolverCage) -> Option<Step> {
+        let candidates: Vec<u16> = cage.cells.iter().map(|&c| self.candidates[c]).collect();
+        let possible = cage_options(cage.sum, &candidates, self.solver.size as u32);
+        let removed: Vec<(usize, u16)> = cage
+            .cells
+            .iter()
+            .zip(possible)
+            .map(|(&c, p)| (c, !p))
+            .collect();
+        let step = self.elimination(technique, &removed)?;
+        let valid = fitting_combinations(cage.sum, &candidates, self.solver.size as u32);
+        let sets: Vec<String> = valid.iter().map(|&m| set_name(m)).collect();
+        let reason = match cage.house {
+            None if cage.cells.len() == 1 => {
+                format!("{} is a single cell", self.cage_name(cage))
+            }
+            None if sets.len() <= 3 => {
+                format!("{} must be {}", self.cage_name(cage), join(&sets, "or"))
+            }
+            None => format!(
+                "{} has {} combinations left",
+                self.cage_name(cage),
+                sets.len()
+            ),
+            Some(h) => {
+                let mut reason = format!(
+                    "{} adds up to {}, which leaves {} for {}",
+                    self.house_name(h),
+                    (1..=self.solver.size as u32).sum::<u32>(),
+                    cage.sum,
+                    self.cell_list(&cage.cells)
+                );
+                if cage.cells.len() > 1 && sets.len() <= 3 {
+                    reason.push_str(&format!(" ({})", join(&sets, "or")));
+                }
+                reason
+            }
+        };
+        Some(self.explain(step, &cage.cells, reason))
+    }
+
+    fn cage_combination(&self) -> Option<Step> {
+        self.solver
+            .cages
+            .iter()
+            .find_map(|cage| self.restrict_to_sum(Technique::CageCombination, cage))
+    }
+
+    fn rule_45(&self) -> Option<Step> {
+        if let Some(step) = self
+            .solver
+            .innies
+            .iter()
+            .find_map(|innie| self.restrict_to_sum(Technique::Rule45, innie))
+        {
+            return Some(step);
+        }
+        let house_sum: u32 = (1..=self.solver.size as u32).sum();
+        for (h, house) in self.solver.houses.iter().enumerate() {
+            let mut total = 0;
+            let mut inside = 0;
+            let mut outside = Vec::new();
+            let mut covering = Vec::new();
+            for cage in &self.solver.cages {
+                if !cage.cells.iter().any(|c| house.contains(c)) {
+                    continue;
+                }
+                covering.extend(&cage.cells);
+                total += cage.sum;
+                inside += cage.cells.iter().filter(|c| house.contains(c)).count();
+                outside.extend(cage.cells.iter().filter(|c| !house.contains(c)));
+            }
+            // every house cell is caged and exactly one caged cell sticks out
+            if inside != house.len() || outside.len() != 1 || total <= house_sum {
+                continue;
+            }
+            let value = total - house_sum;
+            if value > self.solver.size as u32 || !self.is_open(outside[0]) {
+                continue;
+            }
+            let reason = format!(
+                "the cages covering {} add up to {}, {} more than the {} in it, and only {} lies outside",
+                self.house_name(h),
+                total,
+                value,
+                house_sum,
+                cell_name(&self.cell(outside[0]))
+            );
+            return Some(self.explain(
+                self.placement(Technique::Rule45, outside[0], value),
+                &covering,
+                reason,
+            ));
+        }
+        None
+    }
+
+    fn pointing(&self) -> Option<Step> {
+        for (a, house) in self.solver.houses.iter().enumerate() {
+            for d in 1..=self.solver.size as u32 {
+                let places: Vec<usize> = house
+                    .iter()
+                    .copied()
+                    .filter(|&c| self.is_open(c) && self.candidates[c] & 1 << d != 0)
+                    .collect();
+                if places.len() < 2 {
+                    continue;
+                }
+                for (b, other) in self.solver.houses.iter().enumerate() {
+                    if a == b || !places.iter().all(|c| other.contains(c)) {
+                        continue;
+                    }
+                    let removed: Vec<(usize, u16)> = other
+                        .iter()
+                        .filter(|c| !house.contains(c))
+                        .map(|&c| (c, 1 << d))
+                        .collect();
+                    if let Some(step) = self.elimination(Technique::Pointing, &removed) {
+                        let reason = format!(
+                            "{} in {} has to be in {}",
+                            d,
+                            self.house_name(a),
+                            self.house_name(b)
+                        );
+                        return Some(self.explain(step, &places, reason));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn cage_interaction(&self) -> Option<Step> {
+        for cage in &self.solver.cages {
+            let candidates: Vec<u16> = cage.cells.iter().map(|&c| self.candidates[c]).collect();
+            let valid = fitting_combinations(cage.sum, &candidates, self.solver.size as u32);
+            if valid.is_empty() {
+                continue;
+            }
+            let required = valid
+                .iter()
+                .fold(self.solver.all_digits(), |acc, c| acc & c);
+            for d in digits(required) {
+                let places: Vec<usize> = cage
+                    .cells
+                    .iter()
+                    .copied()
+                    .filter(|&c| self.is_open(c) && self.candidates[c] & 1 << d != 0)
+                    .collect();
+                if places.len() < 2 {
+                    continue;
+                }
+                let removed: Vec<(usize, u16)> = self.solver.peers[places[0]]
+                    .iter()
+                    .copied()
+                    .filter(|c| !cage.cells.contains(c))
+                    .filter(|c| places.iter().all(|&p| self.solver.peers[p].contains(c)))
+                    .map(|c| (c, 1 << d))
+                    .collect();
+                if let Some(step) = self.elimination(Technique::CageInteraction, &removed) {
+                    let reason = format!(
+                        "every combination left for {} needs {}, which has to go in {}",
+                        self.cage_name(cage),
+                        d,
+                        join(
+                            &places
+                                .iter()
+                                .map(|&c| cell_name(&self.cell(c)))
+                                .collect::<Vec<_>>(),
+                            "or"
+                        )
+                    );
+                    return Some(self.explain(step, &cage.cells, reason));
+                }
+            }
+        }
+        None
+    }
+
+    fn naked_subset(&self) -> Option<Step> {
+        let houses =
+            (self.solver.houses.iter().enumerate()).map(|(h, house)| (house, self.house_name(h)));
+        let cages = (self.solver.cages.iter()).map(|cage| (&cage.cells, self.cage_name(cage)));
+        for (group, name) in houses.chain(cages) {
+            let open: Vec<usize> = group.iter().copied().filter(|&c| self.is_open(c)).collect();
+            for size in 2..=3 {
+                for subset in subsets(&open, size) {
+                    let mask = subset.iter().fold(0, |acc, &c| acc | self.candidates[c]);
+                    if mask.count_ones() as usize != size {
+                        continue;
+                    }
+                    let removed: Vec<(usize, u16)> = open
+                        .iter()
+                        .filter(|c| !subset.contains(c))
+                        .map(|&c| (c, mask))
+                        .collect();
+                    if let Some(step) = self.elimination(Technique::NakedSubset, &removed) {
+                        let reason = format!(
+                            "{} hold {} between them in {}",
+                            self.cell_list(&subset),
+                            set_name(mask),
+                            name
+                        );
+                        return Some(self.explain(step, &subset, reason));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn hidden_subset(&self) -> Option<Step> {
+        for (h, house) in self.solver.houses.iter().enumerate() {
+            let missing: Vec<usize> = (1..=self.solver.size)
+                .filter(|&d| !house.iter().any(|&c| self.values[c] == d as u32))
+                .collect();
+            for size in 2..=3 {
+                for subset in subsets(&missing, size) {
+                    let mask = subset.iter().fold(0u16, |acc, &d| acc | 1 << d);
+                    let places: Vec<usize> = house
+                        .iter()
+                        .copied()
+                        .filter(|&c| self.is_open(c) && self.candidates[c] & mask != 0)
+                        .collect();
+                    if places.len() != size {
+                        continue;
+                    }
+                    let removed: Vec<(usize, u16)> = places.iter().map(|&c| (c, !mask)).collect();
+                    if let Some(step) = self.elimination(Technique::HiddenSubset, &removed) {
+                        let reason = format!(
+                            "{} only fit {} in {}",
+                            set_name(mask),
+                            self.cell_list(&places),
+                            self.house_name(h)
+                        );
+                        return Some(self.explain(step, &places, reason));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// All subsets of `items` with exactly `size` elements.
+fn subsets(items: &[usize], size: usize) -> Vec<Vec<usize>> {
+    if size == 0 {
+        return vec![vec![]];
+    }
+    let mut result = Vec::new();
+    for (i, &item) in items.iter().enumerate() {
+        for mut rest in subsets(&items[i + 1..], size - 1) {
+            rest.insert(0, item);
+            result.push(rest);
+        }
+    }
+    result
+}
+
+/// A cell as `r1c1`, counting from one.
+fn cell_name(cell: &Cell) -> String {
+    format!("r{}c{}", cell.row + 1, cell.col + 1)
+}
+
+/// A set of digits as `{6,8,9}`.
+fn set_name(mask: u16) -> String {
+    let digits: Vec<String> = digits(mask).map(|d| d.to_string()).collect();
+    format!("{{{}}}", digits.join(","))
+}
+
+/// Joins words as "a, b and c".
+fn join(words: &[String], conjunction: &str) -> String {
+    match words {
+        [] => String::new(),
+        [word] => word.clone(),
+        [rest @ .., last] => format!("{} {} {}", rest.join(", "), conjunction, last),
+    }
+}
+
+/// Digits of a mask as "1, 2 or 3".
+fn digit_list(mask: u16, conjunction: &str) -> String {
+    let digits: Vec<String> = digits(mask).map(|d| d.to_string()).collect();
+    join(&digits, conjunction)
+}
@@ -0,0 +1,427 @@
+//! Plain text format for puzzles, meant to be edited by hand and kept in git.
+//!
+//! ```text
+//! # lines starting with '#' are comments
+//! [size]
+//! 9 3x3        (optional, grid size and box rows x columns)
+//! [cages]
+//! AABBCCDDE
+//! FGGBHIDJE
+//! ...          (one row of labels per grid row)
+//! [sums]
+//! A 10
+//! B 17
+//! ...          (one line per label)
+//! [solution]
+//! 534678912
+//! ...          (optional, one row of digits per grid row)
+//! ```
+//!
+//! Without a `[size]` section the grid is a classic 9x9. A row without
+//! spaces uses one character per cell. When labels are longer than one
+//! character, or digits go past 9, the cells of a row are separated by
+//! spaces instead. A `.` marks a cell outside every cage. Without a
+//! `[solution]` section the solution is found by solving, which fails
+//! unless it is unique.
+//!
+//! Errors name the line and, for a cell, its column in the grid, whether
+//! or not the row is spaced.
+
+use std::collections::{HashMap, HashSet};
+
+use super::{Cage, Cell, Shape, SumdokuBoard};
+
+enum Section {
+    None,
+    Size,
+    Cages,
+    Sums,
+    Solution,
+}
+
+impl SumdokuBoard {
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let mut section = Section::None;
+        let mut shape = Shape::CLASSIC;
+        let mut map: Vec<Vec<(usize, String)>> = Vec::new();
+        let mut map_lines = Vec::new();
+        let mut sums: HashMap<String, (usize, u32)> = HashMap::new();
+        let mut solution: Vec<Vec<u32>> = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let number = number + 1;
+            let line = line.trim_end();
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+            match line.trim() {
+                "[size]" => section = Section::Size,
+                "[cages]" => section = Section::Cages,
+                "[sums]" => section = Section::Sums,
+                "[solution]" => section = Section::Solution,
+                _ => match section {
+                    Section::None => {
+                        return Err(format!("line {}: expected a section header", number))
+                    }
+                    Section::Size => {
+                        if !map.is_empty() || !solution.is_empty() {
+                            return Err(format!("line {}: size must come before the grid", number));
+                        }
+                        shape = parse_shape(line, number)?;
+                    }
+                    Section::Cages => {
+                        if map.len() == shape.size {
+                            return Err(format!(
+                                "line {}: more than {} cage rows",
+                                number, shape.size
+                            ));
+                        }
+                        map.push(parse_row(line, number, shape.size)?);
+                        map_lines.push(number);
+                    }
+                    Section::Sums => {
+                        let (label, sum) = parse_sum(line, number)?;
+                        if sums.insert(label.clone(), (number, sum)).is_some() {
+                            return Err(format!("line {}: sum for {} given twice", number, label));
+                        }
+                    }
+                    Section::Solution => {
+                        if solution.len() == shape.size {
+                            return Err(format!(
+                                "line {}: more than {} solution rows",
+                                number, shape.size
+                            ));
+                        }
+                        solution.push(parse_digits(line, number, shape.size)?);
+                    }
+                },
+            }
+        }
+        if map.len() != shape.size {
+            return Err(format!(
+                "expected {} cage rows, found {}",
+                shape.size,
+                map.len()
+            ));
+        }
+
+        let mut cells: HashMap<&str, Vec<Cell>> = HashMap::new();
+        let mut order = Vec::new();
+        for (r, row) in map.iter().enumerate() {
+            for (c, (_, label)) in row.iter().enumerate() {
+                if label == "." {
+                    continue;
+                }
+                if !cells.contains_key(label.as_str()) {
+                    order.push((r, c, label.as_str()));
+                }
+                cells
+                    .entry(label.as_str())
+                    .or_default()
+                    .push(Cell::new(r, c));
+            }
+        }
+        let mut cages = Vec::new();
+        for (r, c, label) in order {
+            let Some(&(_, sum)) = sums.get(label) else {
+                return Err(format!(
+                    "line {}, column {}: cage {} has no sum",
+                    map_lines[r], map[r][c].0, label
+                ));
+            };
+            let cage = Cage {
+                sum,
+                cells: cells.remove(label).unwrap_or_default(),
+                lines: None,
+            };
+            if !is_connected(&cage) {
+                return Err(format!(
+                    "line {}, column {}: cage {} is not connected",
+                    map_lines[r], map[r][c].0, label
+                ));
+            }
+            cages.push(cage);
+        }
+        let used: HashSet<&str> = map.iter().flatten().map(|(_, l)| l.as_str()).collect();
+        if let Some((label, (number, _))) = sums.iter().find(|(l, _)| !used.contains(l.as_str())) {
+            return Err(format!("line {}: sum for unknown cage {}", number, label));
+        }
+
+        if solution.is_empty() {
+            SumdokuBoard::from_cages(shape, cages)
+        } else if solution.len() != shape.size {
+            Err(format!(
+                "expected {} solution rows, found {}",
+                shape.size,
+                solution.len()
+            ))
+        } else {
+            SumdokuBoard::from_solution(shape, cages, solution)
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let size = self.shape.size;
+        let mut owner = vec![vec![None; size]; size];
+        for (i, cage) in self.cages.iter().enumerate() {
+            for cell in &cage.cells {
+                owner[cell.row][cell.col] = Some(i);
+            }
+        }
+        // cages are labelled in reading order so that parsing and writing
+        // again gives back the same text
+        let mut labels = vec![None; self.cages.len()];
+        let mut order = Vec::new();
+        for &i in owner.iter().flatten().flatten() {
+            if labels[i].is_none() {
+                labels[i] = Some(label(order.len()));
+                order.push(i);
+            }
+        }
+        let separator = if self.cages.len() > 52 { " " } else { "" };
+        let mut text = String::new();
+        if self.shape != Shape::CLASSIC {
+            text.push_str(&format!("[size]\n{}\n", self.shape));
+        }
+        text.push_str("[cages]\n");
+        for row in &owner {
+            let row: Vec<&str> = row
+                .iter()
+                .map(|i| i.and_then(|i| labels[i].as_deref()).unwrap_or("."))
+                .collect();
+            text.push_str(&row.join(separator));
+            text.push('\n');
+        }
+        text.push_str("[sums]\n");
+        for i in order {
+            let label = labels[i].as_deref().unwrap_or_default();
+            text.push_str(&format!("{} {}\n", label, self.cages[i].sum));
+        }
+        if !self.solution.is_empty() {
+            text.push_str("[solution]\n");
+            for row in &self.solution {
+                let digits: Vec<String> = row.iter().map(|d| d.to_string()).collect();
+                text.push_str(&digits.join(if size > 9 { " " } else { "" }));
+                text.push('\n');
+            }
+        }
+        text
+    }
+}
+
+/// Label of the n-th cage: A-Z, then a-z, then two letters.
+fn label(n: usize) -> String {
+    let letter = |i: usize| {
+        if i < 26 {
+            (b'A' + i as u8) as char
+        } else {
+            (b'a' + (i - 26) as u8) as char
+        }
+    };
+    if n < 52 {
+        letter(n).to_string()
+    } else {
+        let n = n - 52;
+        format!("{}{}", letter(n / 52), letter(n % 52))
+    }
+}
+
+/// Splits a row into (column, token) pairs, one token per cell, with the
+/// columns of the grid counted from 1.
+fn parse_row(line: &str, number: usize, size: usize) -> Result<Vec<(usize, String)>, String> {
+    let line = line.trim();
+    let tokens: Vec<String> = if line.contains(char::is_whitespace) {
+        line.split_whitespace().map(str::to_string).collect()
+    } else {
+        line.chars().map(|ch| ch.to_string()).collect()
+    };
+    let row: Vec<(usize, String)> = tokens
+        .into_iter()
+        .enumerate()
+        .map(|(i, token)| (i + 1, token))
+        .collect();
+    if row.len() != size {
+        return Err(format!(
+            "line {}: expected {} cells, found {}",
+            number,
+            size,
+            row.len()
+        ));
+    }
+    if let Some((column, token)) = row
+        .iter()
+        .find(|(_, t)| t != "." && !t.chars().all(|ch| ch.is_ascii_alphanumeric()))
+    {
+        return Err(format!(
+            "line {}, column {}: invalid cage label '{}'",
+            number, column, token
+        ));
+    }
+    Ok(row)
+}
+
+fn parse_sum(line: &str, number: usize) -> Result<(String, u32), String> {
+    let mut parts = line.split_whitespace();
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(label), Some(sum), None) => sum
+            .parse()
+            .map(|sum| (label.to_string(), sum))
+            .map_err(|_| format!("line {}: invalid sum '{}'", number, sum)),
+        _ => Err(format!("line {}: expected '<label> <sum>'", number)),
+    }
+}
+
+fn parse_digits(line: &str, number: usize, size: usize) -> Result<Vec<u32>, String> {
+    parse_row(line, number, size)?
+        .into_iter()
+        .map(|(column, token)| match token.parse::<u32>() {
+            Ok(d) if (1..=size as u32).contains(&d) => Ok(d),
+            _ => Err(format!(
+                "line {}, column {}: '{}' is not a digit from 1 to {}",
+                number, column, token, size
+            )),
+        })
+        .collect()
+}
+
+/// Parses a size line such as `6 2x3`.
+fn parse_shape(line: &str, number: usize) -> Result<Shape, String> {
+    let invalid = || {
+        format!(
+            "line {}: expected '<size> <box rows>x<box columns>'",
+            number
+        )
+    };
+    let mut parts = line.split_whitespace();
+    let (Some(size), Some(boxes), None) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(invalid());
+    };
+    let (rows, cols) = boxes.split_once('x').ok_or_else(invalid)?;
+    let size: usize = size.parse().map_err(|_| invalid())?;
+    let rows: usize = rows.parse().map_err(|_| invalid())?;
+    let cols: usize = cols.parse().map_err(|_| invalid())?;
+    let shape = Shape::new(rows, cols);
+    if shape.size != size || !Shape::SUPPORTED.contains(&shape) {
+        return Err(format!(
+            "line {}: unsupported grid size {}",
+            number,
+            line.trim()
+        ));
+    }
+    Ok(shape)
+}
+
+fn is_connected(cage: &Cage) -> bool {
+    let mut reached = vec![false; cage.cells.len()];
+    let mut stack = vec![0];
+    reached[0] = true;
+    while let Some(i) = stack.pop() {
+        for (j, other) in cage.cells.iter().enumerate() {
+            if !reached[j] && cage.is_adjacent(&cage.cells[i], other) {
+                reached[j] = true;
+                stack.push(j);
+            }
+        }
+    }
+    reached.iter().all(|&r| r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL: &str = "\
+[size]
+4 2x2
+[cages]
+AABB
+AABB
+CCDD
+CCDD
+[sums]
+A 10
+B 10
+C 10
+D 10
+[solution]
+1234
+3412
+2143
+4321
+";
+
+    #[test]
+    fn round_trips_every_size() {
+        for shape in Shape::SUPPORTED {
+            let board = SumdokuBoard::from_seed(2, shape, 6);
+            let text = board.to_text();
+            let parsed = SumdokuBoard::from_text(&text).unwrap();
+            assert_eq!(parsed.shape, shape);
+            assert_eq!(parsed.solution, board.solution);
+            assert_eq!(parsed.to_text(), text);
+        }
+    }
+
+    #[test]
+    fn solves_a_board_without_a_solution() {
+        let board = SumdokuBoard::from_seed(4, Shape::new(2, 3), 4);
+        let text = board.to_text();
+        let cages_only = &text[..text.find("[solution]").unwrap()];
+        let parsed = SumdokuBoard::from_text(cages_only).unwrap();
+        assert_eq!(parsed.solution, board.solution);
+    }
+
+    #[test]
+    fn reads_comments_and_spaced_rows() {
+        let spaced = SMALL
+            .replace("AABB\nAABB", "# the top half\nA A B B\nA A B B")
+            .replace("3412", "3 4 1 2");
+        let board = SumdokuBoard::from_text(&spaced).unwrap();
+        assert_eq!(board.to_text(), SMALL);
+    }
+
+    fn error(text: &str) -> String {
+        SumdokuBoard::from_text(text).err().unwrap()
+    }
+
+    #[test]
+    fn reports_where_errors_are() {
+        assert_eq!(error("AABB\n"), "line 1: expected a section header");
+        assert_eq!(
+            error(&SMALL.replacen("AABB", "AA!B", 1)),
+            "line 4, column 3: invalid cage label '!'"
+        );
+        // columns count cells, not characters
+        assert_eq!(
+            error(&SMALL.replacen("AABB", "A  A B !", 1)),
+            "line 4, column 4: invalid cage label '!'"
+        );
+        assert_eq!(
+            error(&SMALL.replacen("AABB", "A A é B", 1)),
+            "line 4, column 3: invalid cage label 'é'"
+        );
+        assert_eq!(
+            error(&SMALL.replacen("AABB", "AAB", 1)),
+            "line 4: expected 4 cells, found 3"
+        );
+        assert_eq!(
+            error(&SMALL.replace("C 10\n", "")),
+            "line 6, column 1: cage C has no sum"
+        );
+        assert_eq!(
+            error(&SMALL.replace("B 10", "B ten")),
+            "line 10: invalid sum 'ten'"
+        );
+        assert_eq!(
+            error(&SMALL.replace("2143", "2153")),
+            "line 16, column 3: '5' is not a digit from 1 to 4"
+        );
+        assert_eq!(
+            error(&SMALL.replace("4 2x2", "5 2x2")),
+            "line 2: unsupported grid size 5 2x2"
+        );
+        assert_eq!(
+            error(&SMALL.replacen("AABB", "ACBB", 1)),
+            "line 4, column 2: cage C is not connected"
+        );
+    }
+}
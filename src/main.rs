@@ -1,59 +1,114 @@
-extern crate sdl2;
-
-pub mod game;
-pub mod graphics;
-pub mod logic;
-
-use game::Game;
-use graphics::GameRenderer;
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use sdl2::event::{Event, WindowEvent};
 use sdl2::pixels::Color;
 use std::cell::RefCell;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use sumdoku::game::{save, Game};
+use sumdoku::graphics::{Assets, GameRenderer, Layout, Themes};
+use sumdoku::logic::Shape;
+
+/// How often the game in progress is saved.
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
 
 pub fn main() -> Result<(), String> {
     let sdl_context = sdl2::init()?;
     let video_subsystem = sdl_context.video()?;
 
-    let window = video_subsystem
-        .window("Sumdoku a.k.a Killer Sudoku", 730, 1000)
+    // start as large as the display's DPI asks for, as long as it fits
+    let dpi_scale = video_subsystem
+        .display_dpi(0)
+        .map_or(1.0, |(dpi, _, _)| (dpi / 96.0).clamp(1.0, 3.0));
+    let available = video_subsystem
+        .display_usable_bounds(0)
+        .map_or((730, 1040), |bounds| (bounds.width(), bounds.height()));
+    let (width, height) = Layout::window_size(available, dpi_scale);
+    let mut window = video_subsystem
+        .window("Sumdoku a.k.a Killer Sudoku", width, height)
         .position_centered()
+        .resizable()
+        .allow_highdpi()
         .build()
         .map_err(|e| e.to_string())?;
+    let (min_width, min_height) = Layout::minimum_window_size();
+    window
+        .set_minimum_size(min_width, min_height)
+        .map_err(|e| e.to_string())?;
 
     let mut canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
+    let texture_creator = canvas.texture_creator();
+    let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
 
     canvas.set_draw_color(Color::RGB(255, 255, 255));
     canvas.clear();
     canvas.present();
 
     let mut event_pump = sdl_context.event_pump()?;
-    let game = Game::new();
-    let mut renderer = GameRenderer::new(RefCell::new(canvas), RefCell::new(game), 80);
+    // an optional argument picks the grid size, e.g. `sumdoku 6`
+    let shape = std::env::args()
+        .nth(1)
+        .and_then(|arg| arg.parse().ok())
+        .and_then(Shape::for_size)
+        .unwrap_or(Shape::CLASSIC);
+    let game = Game::new(shape);
+    let assets = Assets::new(&ttf_context, &texture_creator);
+    let (themes, error) = Themes::load();
+    if let Some(e) = error {
+        eprintln!("could not read the config: {}", e);
+    }
+    let mut renderer = GameRenderer::new(RefCell::new(canvas), RefCell::new(game), assets, themes);
+    match save::load() {
+        Ok(Some(saved)) if !saved.is_complete() => renderer.offer_resume(saved),
+        Ok(_) => {}
+        Err(e) => eprintln!("could not load the saved game: {}", e),
+    }
+    let mut last_save = Instant::now();
 
     'running: loop {
-        for event in event_pump.poll_iter() {
+        if renderer.needs_redraw() {
+            renderer.render()?;
+        }
+        let until_save = AUTOSAVE_INTERVAL.saturating_sub(last_save.elapsed());
+        let timeout = renderer
+            .until_tick()
+            .map_or(until_save, |tick| tick.min(until_save));
+        // sleep until there is input, the clock ticks or a save is due
+        let first = event_pump.wait_event_timeout(timeout.as_millis() as u32 + 1);
+        for event in first.into_iter().chain(event_pump.poll_iter()) {
             match event {
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => {
-                    break 'running;
-                }
+                Event::Quit { .. } => break 'running,
                 Event::KeyDown {
                     keycode: Some(key),
                     ..
                 } => renderer.handle_keyboard_input(key),
+                Event::Window {
+                    win_event: WindowEvent::FocusLost,
+                    ..
+                } => renderer.pause(),
+                Event::Window {
+                    win_event: WindowEvent::SizeChanged(..),
+                    ..
+                } => renderer.resize(),
+                Event::Window { .. } => renderer.redraw(),
                 Event::MouseButtonDown { x, y, .. } => renderer.handle_click(x, y),
                 _ => {}
             }
         }
 
-        renderer.render()?;
-        ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+        if renderer.should_quit() {
+            break 'running;
+        }
+        if last_save.elapsed() >= AUTOSAVE_INTERVAL {
+            if let Err(e) = renderer.autosave() {
+                eprintln!("could not save the game: {}", e);
+            }
+            last_save = Instant::now();
+        }
     }
 
+    if let Err(e) = renderer.autosave() {
+        eprintln!("could not save the game: {}", e);
+    }
+    if let Err(e) = renderer.save_theme() {
+        eprintln!("could not save the theme: {}", e);
+    }
     Ok(())
 }